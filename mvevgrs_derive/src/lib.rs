@@ -0,0 +1,138 @@
+// Derive macros for the renderer, kept in
+// their own crate since proc macros have
+// to be.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Field, Fields, Index, Member};
+
+//Implements Interpolate for a struct by interpolating each of its fields. Every field has to be
+//Interpolate itself, and can be marked #[flat] to take the provoking vertex's value or
+//#[noperspective] to be interpolated linearly in screen space. Marking a field that is a struct
+//applies to everything inside it. The generated impl names ::mvevgrs::interpolate, so it works in
+//any crate that depends on the renderer as mvevgrs, the renderer included
+#[proc_macro_derive(Interpolate, attributes(flat, noperspective))]
+pub fn derive_interpolate(input : TokenStream) -> TokenStream {
+	let input : DeriveInput = parse_macro_input!(input as DeriveInput);
+
+	match interpolate_impl(&input) {
+		Ok(tokens) => tokens.into(),
+		Err(e) => e.to_compile_error().into(),
+	}
+}
+
+fn interpolate_impl(input : &DeriveInput) -> Result<TokenStream2, syn::Error> {
+	let Data::Struct(data) = &input.data else {
+		return Err(syn::Error::new(
+			input.span(),
+			"Interpolate can only be derived for structs",
+		));
+	};
+
+	let name : &syn::Ident = &input.ident;
+
+	let members : Vec<Member> = data
+		.fields
+		.iter()
+		.enumerate()
+		.map(|(i, f) : (usize, &Field)| -> Member {
+			match &f.ident {
+				Some(ident) => Member::Named(ident.clone()),
+				None => Member::Unnamed(Index::from(i)),
+			}
+		})
+		.collect();
+
+	let values : Vec<TokenStream2> = data
+		.fields
+		.iter()
+		.zip(members.iter())
+		.map(
+			|(f, member) : (&Field, &Member)| -> Result<TokenStream2, syn::Error> {
+				let weights : TokenStream2 = field_weights(f)?;
+
+				Ok(quote! {
+					::mvevgrs::interpolate::Interpolate::interpolate(
+						[&verts[0].#member, &verts[1].#member, &verts[2].#member],
+						#weights,
+					)
+				})
+			},
+		)
+		.collect::<Result<Vec<TokenStream2>, syn::Error>>()?;
+
+	let body : TokenStream2 = match &data.fields {
+		Fields::Named(_) => quote! { #name { #(#members : #values,)* } },
+		Fields::Unnamed(_) => quote! { #name ( #(#values,)* ) },
+		//Nothing to interpolate
+		Fields::Unit => quote! {
+			let _ = (verts, weights);
+
+			#name
+		},
+	};
+
+	//Generic fields have to be Interpolate too
+	let mut generics : syn::Generics = input.generics.clone();
+
+	if !generics.params.is_empty() {
+		let where_clause : &mut syn::WhereClause = generics.make_where_clause();
+
+		for f in data.fields.iter() {
+			let ty : &syn::Type = &f.ty;
+
+			where_clause
+				.predicates
+				.push(syn::parse_quote! { #ty : ::mvevgrs::interpolate::Interpolate });
+		}
+	}
+
+	let (impl_generics, ty_generics, where_clause) : (
+		syn::ImplGenerics,
+		syn::TypeGenerics,
+		Option<&syn::WhereClause>,
+	) = generics.split_for_impl();
+
+	Ok(quote! {
+		impl #impl_generics ::mvevgrs::interpolate::Interpolate
+			for #name #ty_generics #where_clause
+		{
+			fn interpolate(
+				verts : [&#name #ty_generics; 3],
+				weights : &::mvevgrs::interpolate::Weights,
+			) -> #name #ty_generics {
+				#body
+			}
+		}
+	})
+}
+
+//The weights a field gets interpolated with, based on which qualifier it has if any
+fn field_weights(f : &Field) -> Result<TokenStream2, syn::Error> {
+	let mut weights : Option<TokenStream2> = None;
+
+	for attr in f.attrs.iter() {
+		let qualified : TokenStream2 = if attr.path().is_ident("flat") {
+			quote! { &weights.flat() }
+		} else if attr.path().is_ident("noperspective") {
+			quote! { &weights.noperspective() }
+		} else {
+			continue;
+		};
+
+		attr.meta.require_path_only()?;
+
+		if weights.is_some() {
+			return Err(syn::Error::new(
+				attr.span(),
+				"A field can only have one interpolation qualifier",
+			));
+		}
+
+		weights = Some(qualified);
+	}
+
+	Ok(weights.unwrap_or(quote! { weights }))
+}
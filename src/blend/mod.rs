@@ -0,0 +1,226 @@
+// How a mesh's pixels get mixed in with
+// whatever has already been drawn
+// underneath them.
+
+use glam::Vec4;
+
+use crate::pixel::Pixel;
+
+//What a color is multiplied by before the two get combined. "Src" is the pixel being drawn and
+//"Dst" is the one already in the frame buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendFactor {
+	Zero,
+	One,
+	SrcColor,
+	OneMinusSrcColor,
+	DstColor,
+	OneMinusDstColor,
+	SrcAlpha,
+	OneMinusSrcAlpha,
+	DstAlpha,
+	OneMinusDstAlpha,
+}
+
+impl BlendFactor {
+	fn factor(
+		self: BlendFactor,
+		src : Pixel,
+		dst : Pixel,
+	) -> Vec4 {
+		match self {
+			BlendFactor::Zero => Vec4::ZERO,
+			BlendFactor::One => Vec4::ONE,
+			BlendFactor::SrcColor => src,
+			BlendFactor::OneMinusSrcColor => Vec4::ONE - src,
+			BlendFactor::DstColor => dst,
+			BlendFactor::OneMinusDstColor => Vec4::ONE - dst,
+			BlendFactor::SrcAlpha => Vec4::splat(src.w),
+			BlendFactor::OneMinusSrcAlpha => Vec4::splat(1_f32 - src.w),
+			BlendFactor::DstAlpha => Vec4::splat(dst.w),
+			BlendFactor::OneMinusDstAlpha => Vec4::splat(1_f32 - dst.w),
+		}
+	}
+}
+
+//How the scaled colors are combined. Min and Max ignore the factors, same as on GPUs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendOp {
+	Add,
+	//src - dst
+	Subtract,
+	//dst - src
+	ReverseSubtract,
+	Min,
+	Max,
+}
+
+//The blend equation for one part of a pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlendComponent {
+	pub src_factor : BlendFactor,
+	pub dst_factor : BlendFactor,
+	pub op : BlendOp,
+}
+
+impl BlendComponent {
+	pub const REPLACE : BlendComponent = BlendComponent {
+		src_factor : BlendFactor::One,
+		dst_factor : BlendFactor::Zero,
+		op : BlendOp::Add,
+	};
+
+	fn apply(
+		self: &BlendComponent,
+		src : Pixel,
+		dst : Pixel,
+	) -> Vec4 {
+		let s : Vec4 = src * self.src_factor.factor(src, dst);
+		let d : Vec4 = dst * self.dst_factor.factor(src, dst);
+
+		match self.op {
+			BlendOp::Add => s + d,
+			BlendOp::Subtract => s - d,
+			BlendOp::ReverseSubtract => d - s,
+			BlendOp::Min => src.min(dst),
+			BlendOp::Max => src.max(dst),
+		}
+	}
+}
+
+//Separate equations for the rgb and alpha channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlendState {
+	pub color : BlendComponent,
+	pub alpha : BlendComponent,
+}
+
+impl BlendState {
+	//Classic see through, for colors that haven't been multiplied by their alpha
+	pub const ALPHA : BlendState = BlendState {
+		color : BlendComponent {
+			src_factor : BlendFactor::SrcAlpha,
+			dst_factor : BlendFactor::OneMinusSrcAlpha,
+			op : BlendOp::Add,
+		},
+		alpha : BlendComponent {
+			src_factor : BlendFactor::One,
+			dst_factor : BlendFactor::OneMinusSrcAlpha,
+			op : BlendOp::Add,
+		},
+	};
+
+	//Same as ALPHA but for colors that are already multiplied by their alpha
+	pub const PREMULTIPLIED_ALPHA : BlendState = BlendState {
+		color : BlendComponent {
+			src_factor : BlendFactor::One,
+			dst_factor : BlendFactor::OneMinusSrcAlpha,
+			op : BlendOp::Add,
+		},
+		alpha : BlendComponent {
+			src_factor : BlendFactor::One,
+			dst_factor : BlendFactor::OneMinusSrcAlpha,
+			op : BlendOp::Add,
+		},
+	};
+
+	//Light that piles up, like fire or glows
+	pub const ADDITIVE : BlendState = BlendState {
+		color : BlendComponent {
+			src_factor : BlendFactor::SrcAlpha,
+			dst_factor : BlendFactor::One,
+			op : BlendOp::Add,
+		},
+		alpha : BlendComponent {
+			src_factor : BlendFactor::One,
+			dst_factor : BlendFactor::One,
+			op : BlendOp::Add,
+		},
+	};
+
+	//The pixel being drawn mixed with the one under it, clamped so it's still a valid pixel
+	pub fn blend(
+		self: &BlendState,
+		src : Pixel,
+		dst : Pixel,
+	) -> Pixel {
+		let color : Vec4 = self.color.apply(src, dst);
+		let alpha : Vec4 = self.alpha.apply(src, dst);
+
+		color.truncate().extend(alpha.w).clamp(Vec4::ZERO, Vec4::ONE)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use glam::Vec4;
+
+	use super::{BlendComponent, BlendFactor, BlendOp, BlendState};
+	use crate::pixel::Pixel;
+
+	const SRC : Pixel = Vec4::new(1.0, 0.5, 0.25, 0.75);
+	const DST : Pixel = Vec4::new(0.25, 0.5, 1.0, 0.5);
+
+	#[test]
+	fn blend_factors() {
+		let expected : [(BlendFactor, Vec4); 10] = [
+			(BlendFactor::Zero, Vec4::ZERO),
+			(BlendFactor::One, Vec4::ONE),
+			(BlendFactor::SrcColor, SRC),
+			(BlendFactor::OneMinusSrcColor, Vec4::new(0.0, 0.5, 0.75, 0.25)),
+			(BlendFactor::DstColor, DST),
+			(BlendFactor::OneMinusDstColor, Vec4::new(0.75, 0.5, 0.0, 0.5)),
+			(BlendFactor::SrcAlpha, Vec4::splat(0.75)),
+			(BlendFactor::OneMinusSrcAlpha, Vec4::splat(0.25)),
+			(BlendFactor::DstAlpha, Vec4::splat(0.5)),
+			(BlendFactor::OneMinusDstAlpha, Vec4::splat(0.5)),
+		];
+
+		for (factor, expected) in expected {
+			assert_eq!(factor.factor(SRC, DST), expected, "{:?}", factor);
+		}
+	}
+
+	//Min and Max leave the factors out, the rest use them
+	#[test]
+	fn blend_ops() {
+		let component = |op : BlendOp| -> Vec4 {
+			BlendComponent {
+				src_factor : BlendFactor::SrcAlpha,
+				dst_factor : BlendFactor::OneMinusSrcAlpha,
+				op,
+			}
+			.apply(SRC, DST)
+		};
+
+		let (s, d) : (Vec4, Vec4) = (SRC * 0.75, DST * 0.25);
+
+		assert_eq!(component(BlendOp::Add), s + d);
+		assert_eq!(component(BlendOp::Subtract), s - d);
+		assert_eq!(component(BlendOp::ReverseSubtract), d - s);
+		assert_eq!(component(BlendOp::Min), Vec4::new(0.25, 0.5, 0.25, 0.5));
+		assert_eq!(component(BlendOp::Max), Vec4::new(1.0, 0.5, 1.0, 0.75));
+	}
+
+	#[test]
+	fn blend_states() {
+		assert_eq!(
+			BlendState {
+				color : BlendComponent::REPLACE,
+				alpha : BlendComponent::REPLACE,
+			}
+			.blend(SRC, DST),
+			SRC
+		);
+		assert_eq!(
+			BlendState::ALPHA.blend(SRC, DST),
+			Vec4::new(0.8125, 0.5, 0.4375, 0.875)
+		);
+		assert_eq!(
+			BlendState::PREMULTIPLIED_ALPHA.blend(SRC, DST),
+			Vec4::new(1.0, 0.625, 0.5, 0.875)
+		);
+		//Piling up past 1 gets clamped
+		assert_eq!(BlendState::ADDITIVE.blend(SRC, DST), Vec4::new(1.0, 0.875, 1.0, 1.0));
+	}
+}
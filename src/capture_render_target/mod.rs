@@ -0,0 +1,54 @@
+// Render target that keeps copies of
+// the frames it's given in memory, so
+// they can be looked at afterwards.
+
+use crate::pixel::Pixel;
+use crate::render_target::{Frame, RenderTarget};
+
+//An owned copy of a Frame
+#[derive(Clone, Debug)]
+pub struct CapturedFrame {
+	pub width : u32,
+	pub height : u32,
+	pub color : Vec<Pixel>,
+	pub depth : Option<Vec<f32>>,
+}
+
+impl CapturedFrame {
+	pub fn as_frame(self: &CapturedFrame) -> Frame<'_> {
+		Frame {
+			width : self.width,
+			height : self.height,
+			color : &self.color,
+			depth : self.depth.as_deref(),
+		}
+	}
+}
+
+#[derive(Default)]
+pub struct CaptureRenderTarget {
+	//Every frame presented so far, oldest first
+	pub frames : Vec<CapturedFrame>,
+}
+
+impl CaptureRenderTarget {
+	pub fn new() -> CaptureRenderTarget {
+		CaptureRenderTarget::default()
+	}
+}
+
+impl RenderTarget for CaptureRenderTarget {
+	fn present(
+		self: &mut CaptureRenderTarget,
+		frame : &Frame,
+	) -> Result<(), String> {
+		self.frames.push(CapturedFrame {
+			width : frame.width,
+			height : frame.height,
+			color : frame.color.to_vec(),
+			depth : frame.depth.map(<[f32]>::to_vec),
+		});
+
+		Ok(())
+	}
+}
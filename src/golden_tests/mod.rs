@@ -0,0 +1,2033 @@
+// Golden image regression tests. Fixed
+// scenes are rendered headlessly and
+// compared against reference PNGs.
+//
+// References live in ./golden. Running the tests with MVEVGRS_BLESS=1 (re)writes them from
+// whatever the renderer currently produces, so look over the new images before checking them
+// in. When a comparison fails, the rendered frame and an image highlighting the mismatched
+// pixels are written to ./target/golden_diffs.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use glam::{Mat4, Vec2, Vec3};
+use image::{Rgb, RgbImage};
+
+use crate::blend::{BlendComponent, BlendFactor, BlendOp, BlendState};
+use crate::capture_render_target::CaptureRenderTarget;
+use crate::image_render_target::frame_to_image;
+use crate::interpolate::Interpolate;
+use crate::lighting::{Attenuation, Light, LitShader, LitV, SpecularModel};
+use crate::mesh::{
+	CompareFunc,
+	CullMode,
+	DepthState,
+	Derivatives,
+	FrontFace,
+	Geometry,
+	Line,
+	Mesh,
+	Point,
+	PolygonMode,
+	StencilOp,
+	StencilState,
+	Triangle,
+	VertTransOut,
+};
+use crate::obj::ObjModel;
+use crate::pixel::Pixel;
+use crate::post_process::effects::{self, Lut, ToneMapOperator};
+use crate::post_process::fxaa::{fxaa, FxaaQuality};
+use crate::render_target::{run_frames, Frame};
+use crate::renderer::{
+	AntiAliasing,
+	Rasterizer,
+	Drawable,
+	ProvokingVertex,
+	Renderer,
+	RendererSettings,
+	SampleCount,
+	Transparency,
+	UpdateFunc,
+};
+use crate::shader::{
+	demo_texture,
+	BasicP,
+	BasicShader,
+	BasicUniforms,
+	BasicV,
+	Shader,
+	TexturedShader,
+	TexturedV,
+};
+use crate::texture::{Filter, Texture, WrapMode};
+
+//How far apart (out of 255) a channel of a rendered pixel is allowed to be from the reference
+//before it counts as a mismatch, can be overridden with MVEVGRS_GOLDEN_TOLERANCE
+const DEFAULT_TOLERANCE : u8 = 1;
+
+fn golden_dir() -> PathBuf {
+	Path::new(env!("CARGO_MANIFEST_DIR")).join("golden")
+}
+
+fn diff_dir() -> PathBuf {
+	Path::new(env!("CARGO_MANIFEST_DIR")).join("target").join("golden_diffs")
+}
+
+fn tolerance(default : u8) -> u8 {
+	std::env::var("MVEVGRS_GOLDEN_TOLERANCE")
+		.ok()
+		.and_then(|s : String| -> Option<u8> { s.parse().ok() })
+		.unwrap_or(default)
+}
+
+//Renders a single frame of the scene through the same path a headless run would take
+pub fn render_scene(renderer : &mut Renderer) -> RgbImage {
+	let mut target : CaptureRenderTarget = CaptureRenderTarget::new();
+
+	run_frames(renderer, &mut target, 1).expect("Capturing a frame should not fail");
+
+	frame_to_image(&target.frames[0].as_frame())
+}
+
+//Panics if the image differs from the reference called name by more than tolerance in any
+//channel of any pixel
+pub fn assert_golden(
+	name : &str,
+	actual : &RgbImage,
+	tolerance : u8,
+) -> () {
+	let reference_path : PathBuf = golden_dir().join(format!("{}.png", name));
+
+	if std::env::var_os("MVEVGRS_BLESS").is_some() {
+		std::fs::create_dir_all(golden_dir()).expect("Golden directory should be creatable");
+
+		actual
+			.save(&reference_path)
+			.expect("Reference image should be writable");
+
+		return;
+	}
+
+	let reference : RgbImage = image::open(&reference_path)
+		.unwrap_or_else(|e : image::ImageError| -> image::DynamicImage {
+			panic!(
+				"Could not load reference {} ({}), run with MVEVGRS_BLESS=1 to create it",
+				reference_path.display(),
+				e
+			)
+		})
+		.into_rgb8();
+
+	assert_eq!(
+		reference.dimensions(),
+		actual.dimensions(),
+		"{} was rendered at a different size than its reference",
+		name
+	);
+
+	let mut mismatched : u32 = 0;
+
+	//Mismatches are red and as bright as they are wrong, everything else is a faded out copy of
+	//the rendered frame so it's easy to tell where they are
+	let diff : RgbImage =
+		RgbImage::from_fn(actual.width(), actual.height(), |x : u32, y : u32| -> Rgb<u8> {
+			let a : &Rgb<u8> = actual.get_pixel(x, y);
+			let r : &Rgb<u8> = reference.get_pixel(x, y);
+
+			let worst : u8 = a
+				.0
+				.iter()
+				.zip(r.0.iter())
+				.map(|(a, r) : (&u8, &u8)| -> u8 { a.abs_diff(*r) })
+				.max()
+				.unwrap_or(0);
+
+			if worst > tolerance {
+				mismatched += 1;
+
+				Rgb([worst.max(128), 0, 0])
+			} else {
+				let luma : u8 = ((a.0[0] as u32 + a.0[1] as u32 + a.0[2] as u32) / 12) as u8;
+
+				Rgb([luma, luma, luma])
+			}
+		});
+
+	if mismatched > 0 {
+		std::fs::create_dir_all(diff_dir()).expect("Diff directory should be creatable");
+
+		let actual_path : PathBuf = diff_dir().join(format!("{}_actual.png", name));
+		let diff_path : PathBuf = diff_dir().join(format!("{}_diff.png", name));
+
+		actual.save(&actual_path).expect("Rendered image should be writable");
+		diff.save(&diff_path).expect("Diff image should be writable");
+
+		panic!(
+			"{} has {} pixels off by more than {}, see {} and {}",
+			name,
+			mismatched,
+			tolerance,
+			actual_path.display(),
+			diff_path.display()
+		);
+	}
+}
+
+fn basic_renderer<S : Shader>(
+	mesh : Mesh<S>,
+	camera_mat : Mat4,
+) -> Renderer {
+	let mut renderer : Renderer =
+		Renderer::new(RendererSettings::default(), vec![Box::new(mesh)], None);
+
+	renderer.camera.camera_mat = camera_mat;
+
+	renderer
+}
+
+//Where the camera sits to look at a corner of the cube
+const CUBE_CORNER_EYE : Vec3 = Vec3::new(1.3, 1.1, -1.7);
+
+//The cube seen from its corner, with whatever the test is checking changed first
+fn cube_corner_with(configure : impl FnOnce(&mut Renderer) -> ()) -> RgbImage {
+	let mut renderer : Renderer =
+		basic_renderer(unit_cube(), Mat4::look_at_lh(CUBE_CORNER_EYE, Vec3::ZERO, Vec3::Y));
+
+	configure(&mut renderer);
+
+	render_scene(&mut renderer)
+}
+
+//The cube cube_corner_with draws, for tests that change the mesh instead of the renderer
+fn corner_cube(renderer : &mut Renderer) -> &mut Mesh<BasicShader> {
+	renderer.mesh_mut(0).expect("The cube should be the only mesh")
+}
+
+fn unit_cube() -> Mesh<BasicShader> {
+	Mesh::<BasicShader>::unit_cube()
+}
+
+fn unit_plane() -> Mesh<BasicShader> {
+	Mesh::<BasicShader>::unit_plane()
+}
+
+#[test]
+fn cube_front() {
+	let mut renderer : Renderer = basic_renderer(
+		unit_cube(),
+		Mat4::look_at_lh(Vec3::new(0.0, 0.0, -2.0), Vec3::ZERO, Vec3::Y),
+	);
+
+	assert_golden("cube_front", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+#[test]
+fn cube_corner() {
+	let mut renderer : Renderer = basic_renderer(
+		unit_cube(),
+		Mat4::look_at_lh(CUBE_CORNER_EYE, Vec3::ZERO, Vec3::Y),
+	);
+
+	assert_golden("cube_corner", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+//However the screen is split up and however many threads draw it, the frame has to come out
+//exactly the same
+#[test]
+fn cube_corner_tiled() {
+	[(1, 64), (4, 64), (3, 17), (8, 1000)]
+		.into_iter()
+		.for_each(|(threads, tile_size) : (usize, u32)| -> () {
+			let mut renderer : Renderer = basic_renderer(
+				unit_cube(),
+				Mat4::look_at_lh(CUBE_CORNER_EYE, Vec3::ZERO, Vec3::Y),
+			);
+
+			renderer.renderer_settings.threads = threads;
+			renderer.renderer_settings.tile_size = tile_size;
+
+			assert_golden("cube_corner", &render_scene(&mut renderer), 0);
+		});
+}
+
+#[test]
+fn cube_rotated_model() {
+	let mut cube : Mesh<BasicShader> = unit_cube();
+
+	cube.model_mat = Mat4::from_translation(Vec3::new(0.3, -0.2, 2.5))
+		* Mat4::from_rotation_y(0.6)
+		* Mat4::from_rotation_x(0.4);
+
+	let mut renderer : Renderer = basic_renderer(cube, Mat4::IDENTITY);
+
+	assert_golden(
+		"cube_rotated_model",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+//The camera is inside the cube, so every triangle crosses the near plane or the sides of the
+//view frustum and has to be clipped
+#[test]
+fn cube_inside() {
+	let mut renderer : Renderer = basic_renderer(
+		unit_cube(),
+		Mat4::look_at_lh(Vec3::new(0.1, 0.2, -0.3), Vec3::new(0.4, 0.0, 1.0), Vec3::Y),
+	);
+
+	assert_golden("cube_inside", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+//Culling the faces pointing away shouldn't change anything for a closed mesh seen from outside.
+//The scanline rasterizer lets them poke out past the silhouette, so this needs exact coverage
+#[test]
+fn cube_corner_cull_back() {
+	let mut cube : Mesh<BasicShader> = unit_cube();
+
+	cube.cull_mode = CullMode::Back;
+
+	let mut renderer : Renderer = basic_renderer(
+		cube,
+		Mat4::look_at_lh(CUBE_CORNER_EYE, Vec3::ZERO, Vec3::Y),
+	);
+
+	renderer.renderer_settings.rasterizer = Rasterizer::EdgeFunction;
+
+	assert_golden(
+		"cube_corner_edge_function",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+//Only the inside of the far faces should be left
+#[test]
+fn cube_corner_cull_front() {
+	let mut cube : Mesh<BasicShader> = unit_cube();
+
+	cube.cull_mode = CullMode::Front;
+
+	let mut renderer : Renderer = basic_renderer(
+		cube,
+		Mat4::look_at_lh(CUBE_CORNER_EYE, Vec3::ZERO, Vec3::Y),
+	);
+
+	assert_golden(
+		"cube_corner_cull_front",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+//Flipping which winding is the front flips which faces get culled
+#[test]
+fn cube_corner_cull_back_clockwise() {
+	let mut cube : Mesh<BasicShader> = unit_cube();
+
+	cube.cull_mode = CullMode::Back;
+	cube.front_face = FrontFace::Clockwise;
+
+	let mut renderer : Renderer = basic_renderer(
+		cube,
+		Mat4::look_at_lh(CUBE_CORNER_EYE, Vec3::ZERO, Vec3::Y),
+	);
+
+	assert_golden(
+		"cube_corner_cull_front",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+//From inside the cube every face points away, including the ones that need clipping
+#[test]
+fn cube_inside_cull_front() {
+	let mut cube : Mesh<BasicShader> = unit_cube();
+
+	cube.cull_mode = CullMode::Front;
+
+	let mut renderer : Renderer = basic_renderer(
+		cube,
+		Mat4::look_at_lh(Vec3::new(0.1, 0.2, -0.3), Vec3::new(0.4, 0.0, 1.0), Vec3::Y),
+	);
+
+	assert_golden("cube_inside", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+//With no depth testing triangles just cover whatever was drawn before them
+#[test]
+fn cube_corner_depth_always() {
+	let mut cube : Mesh<BasicShader> = unit_cube();
+
+	cube.depth_state = DepthState {
+		compare : CompareFunc::Always,
+		write : false,
+	};
+
+	let mut renderer : Renderer = basic_renderer(
+		cube,
+		Mat4::look_at_lh(CUBE_CORNER_EYE, Vec3::ZERO, Vec3::Y),
+	);
+
+	assert_golden(
+		"cube_corner_depth_always",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+//Flipping the depth test and clearing to the near plane keeps the furthest pixels instead
+#[test]
+fn cube_corner_depth_greater() {
+	let mut cube : Mesh<BasicShader> = unit_cube();
+
+	cube.depth_state.compare = CompareFunc::Greater;
+
+	let mut renderer : Renderer = basic_renderer(
+		cube,
+		Mat4::look_at_lh(CUBE_CORNER_EYE, Vec3::ZERO, Vec3::Y),
+	);
+
+	renderer.renderer_settings.depth_clear = 0.0;
+
+	assert_golden(
+		"cube_corner_depth_greater",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn cube_corner_msaa_2x() {
+	assert_golden(
+		"cube_corner_msaa_2x",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			r.renderer_settings.anti_aliasing = AntiAliasing::Msaa(SampleCount::X2);
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn cube_corner_msaa_4x() {
+	assert_golden(
+		"cube_corner_msaa_4x",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			r.renderer_settings.anti_aliasing = AntiAliasing::Msaa(SampleCount::X4);
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn cube_corner_msaa_8x() {
+	assert_golden(
+		"cube_corner_msaa_8x",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			r.renderer_settings.anti_aliasing = AntiAliasing::Msaa(SampleCount::X8);
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn cube_corner_ssaa_4x() {
+	assert_golden(
+		"cube_corner_ssaa_4x",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			r.renderer_settings.anti_aliasing = AntiAliasing::Ssaa(SampleCount::X4);
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn cube_corner_fxaa_low() {
+	assert_golden(
+		"cube_corner_fxaa_low",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			r.post_process.push(fxaa(FxaaQuality::Low));
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn cube_corner_fxaa_medium() {
+	assert_golden(
+		"cube_corner_fxaa_medium",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			r.post_process.push(fxaa(FxaaQuality::Medium));
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn cube_corner_fxaa_high() {
+	assert_golden(
+		"cube_corner_fxaa_high",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			r.post_process.push(fxaa(FxaaQuality::High));
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+//Passes run in order, each one on what the last one left
+#[test]
+fn post_process_chain() {
+	let mut inverted : RgbImage = cube_corner_with(|r : &mut Renderer| -> () {
+		r.post_process.push(fxaa(FxaaQuality::High));
+		r.post_process.push(Box::new(|frame : &Frame| -> Vec<Pixel> {
+			frame.color.iter().map(|p : &Pixel| -> Pixel { Pixel::ONE - *p }).collect()
+		}));
+	});
+
+	inverted.pixels_mut().for_each(|p : &mut Rgb<u8>| -> () {
+		p.0 = p.0.map(|c : u8| -> u8 { 255 - c });
+	});
+
+	assert_golden("cube_corner_fxaa_high", &inverted, tolerance(DEFAULT_TOLERANCE));
+}
+
+//A pass that loses pixels would leave the frame buffer shorter than the frame
+#[test]
+#[should_panic(expected = "Post process pass 1 should give back one color per pixel")]
+fn post_process_wrong_length() {
+	cube_corner_with(|r : &mut Renderer| -> () {
+		r.post_process.push(effects::gamma(2.2));
+		r.post_process.push(Box::new(|frame : &Frame| -> Vec<Pixel> { frame.color[1..].to_vec() }));
+	});
+}
+
+#[test]
+fn post_process_gamma() {
+	assert_golden(
+		"post_process_gamma",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			r.post_process.push(effects::gamma(2.2));
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn post_process_tone_map() {
+	assert_golden(
+		"post_process_tone_map_reinhard",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			r.post_process.push(effects::tone_map(ToneMapOperator::Reinhard, 2.0));
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+	assert_golden(
+		"post_process_tone_map_aces",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			r.post_process.push(effects::tone_map(ToneMapOperator::Aces, 2.0));
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn post_process_vignette() {
+	assert_golden(
+		"post_process_vignette",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			r.post_process.push(effects::vignette(0.8, 0.3));
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn post_process_color_grade() {
+	//An identity LUT shouldn't change anything
+	assert_golden(
+		"cube_corner",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			r.post_process.push(effects::color_grade(Arc::new(Lut::identity(16))));
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+
+	//Swaps red and blue and warms everything up a bit
+	let lut : Lut = Lut::from_fn(16, |c : Vec3| -> Vec3 {
+		Vec3::new(c.z, c.y, c.x) * Vec3::new(1.0, 0.9, 0.7)
+	});
+
+	assert_golden(
+		"post_process_color_grade",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			r.post_process.push(effects::color_grade(Arc::new(lut)));
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn post_process_blur() {
+	assert_golden(
+		"post_process_blur",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			r.post_process.push(effects::blur(4));
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn post_process_sharpen() {
+	assert_golden(
+		"post_process_sharpen",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			r.post_process.push(effects::sharpen(1.5));
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn cube_corner_wireframe() {
+	assert_golden(
+		"cube_corner_wireframe",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			corner_cube(r).polygon_mode = PolygonMode::Wireframe;
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+//Edges of the faces pointing away go with them
+#[test]
+fn cube_corner_wireframe_cull_back() {
+	assert_golden(
+		"cube_corner_wireframe_cull_back",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			corner_cube(r).polygon_mode = PolygonMode::Wireframe;
+			corner_cube(r).cull_mode = CullMode::Back;
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn cube_corner_points() {
+	assert_golden(
+		"cube_corner_points",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			corner_cube(r).polygon_mode = PolygonMode::Points;
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+//The edges in front should all show up on top of the faces, the ones behind should stay hidden
+#[test]
+fn cube_corner_fill_wireframe() {
+	assert_golden(
+		"cube_corner_fill_wireframe",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			corner_cube(r).polygon_mode = PolygonMode::FillWireframe;
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+//The renderer wide setting wins over the mesh's own mode
+#[test]
+fn polygon_mode_override() {
+	let image : RgbImage = cube_corner_with(|r : &mut Renderer| -> () {
+		r.renderer_settings.polygon_mode = Some(PolygonMode::Wireframe);
+	});
+
+	assert_golden("cube_corner_wireframe", &image, 0);
+}
+
+//Edges crossing the near plane and the sides of the view have to be clipped like triangles
+#[test]
+fn cube_inside_wireframe() {
+	let mut cube : Mesh<BasicShader> = unit_cube();
+
+	cube.polygon_mode = PolygonMode::Wireframe;
+
+	let mut renderer : Renderer = basic_renderer(
+		cube,
+		Mat4::look_at_lh(Vec3::new(0.1, 0.2, -0.3), Vec3::new(0.4, 0.0, 1.0), Vec3::Y),
+	);
+
+	assert_golden(
+		"cube_inside_wireframe",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+//Thick wireframe edges spread out sideways but still end at the corners
+#[test]
+fn cube_corner_wireframe_thick() {
+	assert_golden(
+		"cube_corner_wireframe_thick",
+		&cube_corner_with(|r : &mut Renderer| -> () {
+			corner_cube(r).polygon_mode = PolygonMode::FillWireframe;
+			corner_cube(r).line_width = 3.0;
+		}),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+fn basic_mesh(geometry : Geometry<BasicV>) -> Mesh<BasicShader> {
+	Mesh::new(geometry, BasicShader, Mat4::IDENTITY)
+}
+
+fn basic_line(
+	from : Vec3,
+	to : Vec3,
+	color : Vec3,
+) -> Line<BasicV> {
+	Line::new(
+		BasicV {
+			position : from,
+			color,
+		},
+		BasicV {
+			position : to,
+			color : color * 0.25,
+		},
+	)
+}
+
+//Axes poking out of the cube and a box around it. The parts of the axes inside the cube have to
+//be hidden by it
+#[test]
+fn debug_lines() {
+	let mut axes : Mesh<BasicShader> = basic_mesh(Geometry::Lines(
+		[Vec3::X, Vec3::Y, Vec3::Z]
+			.into_iter()
+			.map(|axis : Vec3| -> Line<BasicV> { basic_line(axis * -1.2, axis * 1.2, axis) })
+			.collect(),
+	));
+
+	axes.line_width = 3.0;
+
+	let corners : [Vec3; 8] = [0, 1, 2, 3, 4, 5, 6, 7].map(|i : u32| -> Vec3 {
+		Vec3::new(
+			if i & 1 == 0 { -0.7 } else { 0.7 },
+			if i & 2 == 0 { -0.7 } else { 0.7 },
+			if i & 4 == 0 { -0.7 } else { 0.7 },
+		)
+	});
+
+	//Corners that only differ in one axis are joined up
+	let bounding_box : Mesh<BasicShader> = basic_mesh(Geometry::Lines(
+		(0..8_usize)
+			.flat_map(|i : usize| -> Vec<Line<BasicV>> {
+				[1, 2, 4]
+					.into_iter()
+					.filter(|bit : &usize| -> bool { i & bit == 0 })
+					.map(|bit : usize| -> Line<BasicV> {
+						basic_line(corners[i], corners[i | bit], Vec3::ONE)
+					})
+					.collect()
+			})
+			.collect(),
+	));
+
+	let mut renderer : Renderer = Renderer::new(
+		RendererSettings::default(),
+		vec![Box::new(unit_cube()), Box::new(axes), Box::new(bounding_box)],
+		None,
+	);
+
+	renderer.camera.camera_mat =
+		Mat4::look_at_lh(CUBE_CORNER_EYE, Vec3::ZERO, Vec3::Y);
+
+	assert_golden("debug_lines", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+//A trajectory that starts behind the camera, so its first segment has to be clipped against the
+//near plane
+#[test]
+fn debug_lines_clipped() {
+	let path : Vec<Vec3> = (0..24)
+		.map(|i : i32| -> Vec3 {
+			let t : f32 = i as f32 / 4.0;
+
+			Vec3::new(t.sin() * 0.8, t.cos() * 0.5, t - 2.5)
+		})
+		.collect();
+
+	//Fades from orange to yellow along the path
+	let verts : Vec<BasicV> = path
+		.iter()
+		.enumerate()
+		.map(|(i, p) : (usize, &Vec3)| -> BasicV {
+			BasicV {
+				position : *p,
+				color : Vec3::new(1.0, 0.4 + i as f32 / 40.0, 0.0),
+			}
+		})
+		.collect();
+
+	let mut trajectory : Mesh<BasicShader> = basic_mesh(Geometry::Lines(
+		verts
+			.windows(2)
+			.map(|w : &[BasicV]| -> Line<BasicV> { Line::new(w[0], w[1]) })
+			.collect(),
+	));
+
+	trajectory.line_width = 2.0;
+
+	let mut renderer : Renderer = basic_renderer(
+		trajectory,
+		Mat4::look_at_lh(Vec3::new(0.0, 0.0, -2.0), Vec3::ZERO, Vec3::Y),
+	);
+
+	assert_golden(
+		"debug_lines_clipped",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+//A sphere of points around the cube, the ones behind it should be hidden
+#[test]
+fn point_cloud() {
+	let mut cloud : Mesh<BasicShader> = basic_mesh(Geometry::Points(
+		(0..200)
+			.map(|i : i32| -> Point<BasicV> {
+				//Spiral down a sphere so the points are spread out evenly
+				let y : f32 = 1.0 - (i as f32 + 0.5) / 100.0;
+				let angle : f32 = i as f32 * 2.399963;
+				let r : f32 = (1.0 - y * y).sqrt();
+
+				let position : Vec3 = Vec3::new(angle.cos() * r, y, angle.sin() * r) * 0.9;
+
+				Point(BasicV {
+					position,
+					color : position * 0.5 + 0.5,
+				})
+			})
+			.collect(),
+	));
+
+	cloud.point_size = 5.0;
+
+	let mut renderer : Renderer = Renderer::new(
+		RendererSettings::default(),
+		vec![Box::new(unit_cube()), Box::new(cloud)],
+		None,
+	);
+
+	renderer.camera.camera_mat =
+		Mat4::look_at_lh(CUBE_CORNER_EYE, Vec3::ZERO, Vec3::Y);
+
+	assert_golden("point_cloud", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+#[test]
+fn cube_tri_div() {
+	let mut renderer : Renderer = basic_renderer(
+		unit_cube(),
+		Mat4::look_at_lh(CUBE_CORNER_EYE, Vec3::ZERO, Vec3::Y),
+	);
+
+	renderer.renderer_settings.show_tri_div = true;
+
+	assert_golden("cube_tri_div", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+#[test]
+fn cube_corner_edge_function() {
+	let mut renderer : Renderer = basic_renderer(
+		unit_cube(),
+		Mat4::look_at_lh(CUBE_CORNER_EYE, Vec3::ZERO, Vec3::Y),
+	);
+
+	renderer.renderer_settings.rasterizer = Rasterizer::EdgeFunction;
+
+	assert_golden(
+		"cube_corner_edge_function",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn cube_tri_div_edge_function() {
+	let mut renderer : Renderer = basic_renderer(
+		unit_cube(),
+		Mat4::look_at_lh(CUBE_CORNER_EYE, Vec3::ZERO, Vec3::Y),
+	);
+
+	renderer.renderer_settings.show_tri_div = true;
+	renderer.renderer_settings.rasterizer = Rasterizer::EdgeFunction;
+
+	assert_golden(
+		"cube_tri_div_edge_function",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+//Vertex positions are already in clip space, and every pixel shaded is counted
+#[derive(Default)]
+struct ClipSpaceShader {
+	shaded : AtomicUsize,
+}
+
+impl Shader for ClipSpaceShader {
+	type Vertex = BasicV;
+	type Varying = BasicP;
+	type Uniforms = ();
+
+	fn uniforms(
+		self: &ClipSpaceShader,
+		_mesh : &Mesh<ClipSpaceShader>,
+		_renderer : &Renderer,
+	) -> () {
+	}
+
+	fn transform_vertex(
+		self: &ClipSpaceShader,
+		vertex : &BasicV,
+		_uniforms : &(),
+	) -> VertTransOut<BasicP> {
+		VertTransOut {
+			pos : vertex.position.extend(1.0),
+			varying : BasicP {
+				color : vertex.color,
+			},
+		}
+	}
+
+	fn color_pixel(
+		self: &ClipSpaceShader,
+		varying : &BasicP,
+		_derivs : &Derivatives<BasicP>,
+		_uniforms : &(),
+	) -> Pixel {
+		self.shaded.fetch_add(1, Ordering::Relaxed);
+
+		varying.color.extend(1.0)
+	}
+}
+
+//Two triangles covering the whole screen that meet along a diagonal, the second one in front of
+//the first so any pixel they both claim gets shaded twice
+#[test]
+fn edge_function_shared_edge() {
+	let corner = |x : f32, y : f32, z : f32| -> BasicV {
+		BasicV {
+			position : Vec3::new(x, y, z),
+			color : Vec3::ONE,
+		}
+	};
+
+	let halves : Mesh<ClipSpaceShader> = Mesh::new(
+		Geometry::Triangles(vec![
+			Triangle::new(corner(-1.0, -1.0, 0.5), corner(1.0, 1.0, 0.5), corner(1.0, -1.0, 0.5)),
+			Triangle::new(
+				corner(-1.0, -1.0, 0.25),
+				corner(-1.0, 1.0, 0.25),
+				corner(1.0, 1.0, 0.25),
+			),
+		]),
+		ClipSpaceShader::default(),
+		Mat4::IDENTITY,
+	);
+
+	let mut renderer : Renderer = basic_renderer(halves, Mat4::IDENTITY);
+
+	renderer.renderer_settings.rasterizer = Rasterizer::EdgeFunction;
+
+	let image : RgbImage = render_scene(&mut renderer);
+
+	assert!(
+		image.pixels().all(|p : &Rgb<u8>| -> bool { *p == Rgb([255, 255, 255]) }),
+		"Every pixel should be covered"
+	);
+
+	let halves : &Mesh<ClipSpaceShader> =
+		renderer.mesh::<ClipSpaceShader>(0).expect("The halves should be the only mesh");
+
+	assert_eq!(
+		halves.shader.shaded.load(Ordering::Relaxed),
+		(image.width() * image.height()) as usize,
+		"Every pixel should be shaded exactly once"
+	);
+}
+
+//A triangle indexing past the end of the vertices trips an assertion in debug builds, release
+//builds leave it out and still draw the rest of the mesh
+#[test]
+#[cfg_attr(debug_assertions, should_panic(expected = "indexes past the end of 3 vertices"))]
+fn indexed_out_of_range() {
+	let corner = |x : f32, y : f32| -> BasicV {
+		BasicV {
+			position : Vec3::new(x, y, 0.5),
+			color : Vec3::ONE,
+		}
+	};
+
+	let mesh : Mesh<ClipSpaceShader> = Mesh::new(
+		Geometry::Indexed {
+			vertices : vec![corner(-1.0, -1.0), corner(-1.0, 1.0), corner(1.0, -1.0)],
+			indices : vec![[0, 1, 3], [0, 1, 2]],
+		},
+		ClipSpaceShader::default(),
+		Mat4::IDENTITY,
+	);
+
+	let image : RgbImage = render_scene(&mut basic_renderer(mesh, Mat4::IDENTITY));
+
+	assert_eq!(*image.get_pixel(0, image.height() - 1), Rgb([255, 255, 255]));
+	assert_ne!(*image.get_pixel(image.width() - 1, 0), Rgb([255, 255, 255]));
+}
+
+//A floor stretching off into the distance, to catch perspective interpolation going wrong
+#[test]
+fn plane_floor() {
+	let mut plane : Mesh<BasicShader> = unit_plane();
+
+	plane.model_mat = Mat4::from_scale(Vec3::new(4.0, 1.0, 16.0));
+
+	let mut renderer : Renderer = basic_renderer(
+		plane,
+		Mat4::look_at_lh(Vec3::new(0.0, 0.5, -8.0), Vec3::new(0.0, 0.0, 0.0), Vec3::Y),
+	);
+
+	assert_golden("plane_floor", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+#[test]
+fn obj_pyramid() {
+	let model : ObjModel = ObjModel::load(&golden_dir().join("models").join("pyramid.obj"))
+		.unwrap_or_else(|e : String| -> ObjModel { panic!("{}", e) });
+
+	let mut renderer : Renderer = basic_renderer(
+		model.to_basic_mesh(),
+		Mat4::look_at_lh(Vec3::new(1.0, 1.2, -1.5), Vec3::new(0.0, 0.3, 0.0), Vec3::Y),
+	);
+
+	assert_golden("obj_pyramid", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+//The vertex colors, half see through
+struct GlassShader;
+
+impl Shader for GlassShader {
+	type Vertex = BasicV;
+	type Varying = BasicP;
+	type Uniforms = BasicUniforms;
+
+	fn uniforms(
+		self: &GlassShader,
+		mesh : &Mesh<GlassShader>,
+		renderer : &Renderer,
+	) -> BasicUniforms {
+		BasicUniforms::new(mesh, renderer)
+	}
+
+	fn transform_vertex(
+		self: &GlassShader,
+		vertex : &BasicV,
+		uniforms : &BasicUniforms,
+	) -> VertTransOut<BasicP> {
+		BasicShader.transform_vertex(vertex, uniforms)
+	}
+
+	fn color_pixel(
+		self: &GlassShader,
+		varying : &BasicP,
+		_derivs : &Derivatives<BasicP>,
+		_uniforms : &BasicUniforms,
+	) -> Pixel {
+		varying.color.extend(0.5)
+	}
+}
+
+//One color all over
+struct FlatShader {
+	color : Pixel,
+}
+
+impl Shader for FlatShader {
+	type Vertex = BasicV;
+	type Varying = BasicP;
+	type Uniforms = BasicUniforms;
+
+	fn uniforms(
+		self: &FlatShader,
+		mesh : &Mesh<FlatShader>,
+		renderer : &Renderer,
+	) -> BasicUniforms {
+		BasicUniforms::new(mesh, renderer)
+	}
+
+	fn transform_vertex(
+		self: &FlatShader,
+		vertex : &BasicV,
+		uniforms : &BasicUniforms,
+	) -> VertTransOut<BasicP> {
+		BasicShader.transform_vertex(vertex, uniforms)
+	}
+
+	fn color_pixel(
+		self: &FlatShader,
+		_varying : &BasicP,
+		_derivs : &Derivatives<BasicP>,
+		_uniforms : &BasicUniforms,
+	) -> Pixel {
+		self.color
+	}
+}
+
+//A unit plane stood up to face the camera, see through and scaled by scale
+fn glass_pane(
+	translation : Vec3,
+	scale : f32,
+	blend_state : BlendState,
+) -> Mesh<GlassShader> {
+	let mut pane : Mesh<GlassShader> = unit_plane().with_shader(GlassShader);
+
+	pane.model_mat = Mat4::from_translation(translation)
+		* Mat4::from_rotation_x(-std::f32::consts::FRAC_PI_2)
+		* Mat4::from_scale(Vec3::splat(scale));
+	pane.depth_state.write = false;
+	pane.blend_state = Some(blend_state);
+
+	pane
+}
+
+//Panes in front of and behind an opaque cube, handed to the renderer in the wrong order
+fn glass_scene(blend_state : BlendState) -> Vec<Box<dyn Drawable>> {
+	vec![
+		Box::new(glass_pane(Vec3::new(0.2, 0.0, -1.0), 1.0, blend_state)),
+		Box::new(glass_pane(Vec3::new(0.0, 0.0, 1.0), 3.0, blend_state)),
+		Box::new(unit_cube()),
+	]
+}
+
+fn glass_renderer(meshes : Vec<Box<dyn Drawable>>) -> Renderer {
+	let mut renderer : Renderer = Renderer::new(RendererSettings::default(), meshes, None);
+
+	renderer.camera.camera_mat =
+		Mat4::look_at_lh(Vec3::new(0.6, 0.5, -2.5), Vec3::ZERO, Vec3::Y);
+	//The scanline rasterizer draws some pixels on shared edges twice, which blending shows
+	renderer.renderer_settings.rasterizer = Rasterizer::EdgeFunction;
+
+	renderer
+}
+
+#[test]
+fn blend_alpha() {
+	let mut renderer : Renderer = glass_renderer(glass_scene(BlendState::ALPHA));
+
+	assert_golden("blend_alpha", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+//Blended meshes are sorted by distance, so the order they're given in doesn't matter
+#[test]
+fn blend_alpha_reversed() {
+	let mut meshes : Vec<Box<dyn Drawable>> = glass_scene(BlendState::ALPHA);
+
+	meshes.reverse();
+
+	let mut renderer : Renderer = glass_renderer(meshes);
+
+	assert_golden("blend_alpha", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+#[test]
+fn blend_additive() {
+	let mut renderer : Renderer = glass_renderer(glass_scene(BlendState::ADDITIVE));
+
+	assert_golden("blend_additive", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+#[test]
+fn blend_min() {
+	let mut renderer : Renderer = glass_renderer(glass_scene(BlendState {
+		color : BlendComponent {
+			src_factor : BlendFactor::One,
+			dst_factor : BlendFactor::One,
+			op : BlendOp::Min,
+		},
+		alpha : BlendComponent::REPLACE,
+	}));
+
+	assert_golden("blend_min", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+//Without any see through meshes crossing each other, per pixel sorting matches sorting meshes
+#[test]
+fn blend_alpha_fragment_lists() {
+	let mut renderer : Renderer = glass_renderer(glass_scene(BlendState::ALPHA));
+
+	renderer.renderer_settings.transparency = Transparency::FragmentLists;
+
+	assert_golden("blend_alpha", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+//Two panes cutting through each other in front of a cube, which no mesh order can get right
+fn crossed_panes() -> Vec<Box<dyn Drawable>> {
+	let mut glass : Mesh<GlassShader> = glass_pane(Vec3::ZERO, 1.5, BlendState::ALPHA);
+	//A flat color so it's easy to tell which pane is in front
+	let mut flat : Mesh<FlatShader> =
+		glass_pane(Vec3::ZERO, 1.5, BlendState::ALPHA).with_shader(FlatShader {
+			color : Pixel::new(1.0, 0.9, 0.0, 0.5),
+		});
+	let mut cube : Mesh<BasicShader> = unit_cube();
+
+	glass.model_mat = Mat4::from_rotation_y(0.8) * glass.model_mat;
+	flat.model_mat = Mat4::from_rotation_y(-0.8) * flat.model_mat;
+	cube.model_mat = Mat4::from_translation(Vec3::new(0.3, 0.0, 1.2));
+
+	vec![Box::new(glass), Box::new(flat), Box::new(cube)]
+}
+
+#[test]
+fn blend_crossed_sorted_meshes() {
+	let mut renderer : Renderer = glass_renderer(crossed_panes());
+
+	assert_golden(
+		"blend_crossed_sorted_meshes",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn blend_crossed_fragment_lists() {
+	let mut renderer : Renderer = glass_renderer(crossed_panes());
+
+	renderer.renderer_settings.transparency = Transparency::FragmentLists;
+
+	assert_golden(
+		"blend_crossed_fragment_lists",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+fn stencil_renderer(meshes : Vec<Box<dyn Drawable>>) -> Renderer {
+	let mut renderer : Renderer = Renderer::new(RendererSettings::default(), meshes, None);
+
+	renderer.camera.camera_mat =
+		Mat4::look_at_lh(CUBE_CORNER_EYE, Vec3::ZERO, Vec3::Y);
+	//Pixels on shared edges have to be counted once for the stencil values to add up
+	renderer.renderer_settings.rasterizer = Rasterizer::EdgeFunction;
+
+	renderer
+}
+
+//The cube only shows up through an invisible pane that marked the stencil buffer
+#[test]
+fn stencil_portal() {
+	//Drawn in the background color so only the stencil buffer can tell it's there
+	let mut portal : Mesh<FlatShader> = unit_plane().with_shader(FlatShader {
+		color : RendererSettings::default().background_col,
+	});
+
+	portal.model_mat = Mat4::from_translation(Vec3::new(0.2, 0.1, -0.8))
+		* Mat4::from_rotation_x(-std::f32::consts::FRAC_PI_2)
+		* Mat4::from_scale(Vec3::splat(0.6));
+	portal.depth_state.write = false;
+	portal.stencil_state = Some(StencilState {
+		reference : 1,
+		pass : StencilOp::Replace,
+		..StencilState::default()
+	});
+
+	let mut cube : Mesh<BasicShader> = unit_cube();
+
+	cube.stencil_state = Some(StencilState {
+		compare : CompareFunc::Equal,
+		reference : 1,
+		..StencilState::default()
+	});
+
+	let mut renderer : Renderer = stencil_renderer(vec![Box::new(portal), Box::new(cube)]);
+
+	assert_golden("stencil_portal", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+//A slightly bigger flat colored cube drawn only where the real one isn't
+#[test]
+fn stencil_outline() {
+	let mut cube : Mesh<BasicShader> = unit_cube();
+
+	cube.stencil_state = Some(StencilState {
+		reference : 1,
+		pass : StencilOp::Replace,
+		..StencilState::default()
+	});
+
+	let mut outline : Mesh<FlatShader> = unit_cube().with_shader(FlatShader {
+		color : Pixel::new(1.0, 0.5, 0.0, 1.0),
+	});
+
+	outline.model_mat = Mat4::from_scale(Vec3::splat(1.1));
+	outline.depth_state.compare = CompareFunc::Always;
+	outline.stencil_state = Some(StencilState {
+		compare : CompareFunc::NotEqual,
+		reference : 1,
+		..StencilState::default()
+	});
+
+	let mut renderer : Renderer = stencil_renderer(vec![Box::new(cube), Box::new(outline)]);
+
+	assert_golden("stencil_outline", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+//Every face of the cube in front of a pixel bumps its stencil value, so the stencil buffer
+//counts how many times the cube was drawn over each pixel
+#[test]
+fn stencil_increment() {
+	let mut cube : Mesh<BasicShader> = unit_cube();
+
+	cube.depth_state.compare = CompareFunc::Always;
+	cube.stencil_state = Some(StencilState {
+		pass : StencilOp::IncrementClamp,
+		..StencilState::default()
+	});
+
+	let mut renderer : Renderer = stencil_renderer(vec![Box::new(cube)]);
+
+	renderer.draw();
+
+	//Seen from outside, a closed convex mesh covers every pixel it's on exactly twice
+	assert!(renderer.stencil_buffer.iter().all(|s : &u8| -> bool { *s == 0 || *s == 2 }));
+	assert!(renderer.stencil_buffer.contains(&2));
+}
+
+type TexturedMesh = Mesh<TexturedShader>;
+
+fn demo_texture_with(
+	filter : Filter,
+	wrap : WrapMode,
+) -> TexturedShader {
+	let mut texture : Texture = (*demo_texture()).clone();
+
+	texture.filter = filter;
+	texture.wrap = wrap;
+
+	TexturedShader {
+		texture : Arc::new(texture),
+	}
+}
+
+fn textured_cube_corner(mesh : TexturedMesh) -> Renderer {
+	basic_renderer(
+		mesh,
+		Mat4::look_at_lh(Vec3::new(1.0, 0.9, -1.4), Vec3::ZERO, Vec3::Y),
+	)
+}
+
+#[test]
+fn textured_cube_bilinear() {
+	let mut renderer : Renderer = textured_cube_corner(
+		Mesh::<TexturedShader>::textured_cube(),
+	);
+
+	assert_golden(
+		"textured_cube_bilinear",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn textured_cube_nearest() {
+	let mut cube : TexturedMesh = Mesh::<TexturedShader>::textured_cube();
+
+	cube.shader = demo_texture_with(Filter::Nearest, WrapMode::Repeat);
+
+	let mut renderer : Renderer = textured_cube_corner(cube);
+
+	assert_golden(
+		"textured_cube_nearest",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+//A quad facing the camera whose texture coordinates go from -1 to 2, so the middle ninth of it
+//is the texture proper and the rest is down to the wrap mode
+fn wrap_quad(shader : TexturedShader) -> TexturedMesh {
+	let corner = |x : f32, y : f32| -> TexturedV {
+		TexturedV {
+			position : Vec3::new(x, y, 0.0),
+			tex_coord : Vec2::new(x, y) * 3.0 + 0.5,
+		}
+	};
+
+	Mesh::new(
+		Geometry::Triangles(vec![
+			Triangle::new(corner(-0.5, -0.5), corner(0.5, 0.5), corner(-0.5, 0.5)),
+			Triangle::new(corner(-0.5, -0.5), corner(0.5, -0.5), corner(0.5, 0.5)),
+		]),
+		shader,
+		Mat4::IDENTITY,
+	)
+}
+
+fn wrap_quad_renderer(shader : TexturedShader) -> Renderer {
+	basic_renderer(
+		wrap_quad(shader),
+		Mat4::look_at_lh(Vec3::new(0.0, 0.0, -0.7), Vec3::ZERO, Vec3::Y),
+	)
+}
+
+#[test]
+fn texture_wrap_repeat() {
+	let mut renderer : Renderer =
+		wrap_quad_renderer(demo_texture_with(Filter::Nearest, WrapMode::Repeat));
+
+	assert_golden(
+		"texture_wrap_repeat",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn texture_wrap_clamp() {
+	let mut renderer : Renderer =
+		wrap_quad_renderer(demo_texture_with(Filter::Nearest, WrapMode::Clamp));
+
+	assert_golden(
+		"texture_wrap_clamp",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn texture_wrap_mirror() {
+	let mut renderer : Renderer =
+		wrap_quad_renderer(demo_texture_with(Filter::Nearest, WrapMode::Mirror));
+
+	assert_golden(
+		"texture_wrap_mirror",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+//Black and white checkerboard, small enough that a large floor squashes a lot of it into every
+//pixel in the distance
+fn checkerboard_with(filter : Filter) -> TexturedShader {
+	let texels : Vec<Pixel> = (0..64)
+		.flat_map(|y : u32| -> Vec<Pixel> {
+			(0..64)
+				.map(|x : u32| -> Pixel {
+					if (x / 8 + y / 8).is_multiple_of(2) { Pixel::ONE } else { Pixel::W }
+				})
+				.collect()
+		})
+		.collect();
+
+	let mut texture : Texture = Texture::new(64, 64, texels);
+
+	texture.filter = filter;
+
+	TexturedShader {
+		texture : Arc::new(texture),
+	}
+}
+
+//A floor that goes a long way off into the distance with the checkerboard tiled across it
+fn checkerboard_floor_renderer(shader : TexturedShader) -> Renderer {
+	let corner = |x : f32, z : f32| -> TexturedV {
+		TexturedV {
+			position : Vec3::new(x, 0.0, z),
+			tex_coord : Vec2::new(x, z) * 0.5,
+		}
+	};
+
+	let floor : TexturedMesh = Mesh::new(
+		Geometry::Triangles(vec![
+			Triangle::new(corner(-8.0, -2.0), corner(8.0, 60.0), corner(-8.0, 60.0)),
+			Triangle::new(corner(-8.0, -2.0), corner(8.0, -2.0), corner(8.0, 60.0)),
+		]),
+		shader,
+		Mat4::IDENTITY,
+	);
+
+	basic_renderer(
+		floor,
+		Mat4::look_at_lh(Vec3::new(0.0, 1.0, -2.0), Vec3::new(0.0, 0.0, 6.0), Vec3::Y),
+	)
+}
+
+#[test]
+fn floor_bilinear() {
+	let mut renderer : Renderer =
+		checkerboard_floor_renderer(checkerboard_with(Filter::Bilinear));
+
+	assert_golden("floor_bilinear", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+#[test]
+fn floor_trilinear() {
+	let mut renderer : Renderer =
+		checkerboard_floor_renderer(checkerboard_with(Filter::Trilinear));
+
+	assert_golden("floor_trilinear", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+#[test]
+fn floor_anisotropic() {
+	let mut renderer : Renderer =
+		checkerboard_floor_renderer(checkerboard_with(Filter::Anisotropic(8)));
+
+	assert_golden(
+		"floor_anisotropic",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+//A textured cube and a vertex colored one cutting through each other with a line gizmo on top,
+//all sharing one depth buffer even though each has its own shader
+#[test]
+fn mixed_shaders() {
+	let mut textured : TexturedMesh = Mesh::<TexturedShader>::textured_cube();
+	let mut colored : Mesh<BasicShader> = unit_cube();
+
+	textured.model_mat = Mat4::from_translation(Vec3::new(-0.3, 0.0, 0.0));
+	colored.model_mat =
+		Mat4::from_translation(Vec3::new(0.3, 0.1, 0.2)) * Mat4::from_rotation_y(0.6);
+
+	let axes : Mesh<BasicShader> = basic_mesh(Geometry::Lines(
+		[Vec3::X, Vec3::Y, Vec3::Z]
+			.into_iter()
+			.map(|axis : Vec3| -> Line<BasicV> { basic_line(Vec3::ZERO, axis * 1.2, axis) })
+			.collect(),
+	));
+
+	let mut renderer : Renderer = Renderer::new(
+		RendererSettings::default(),
+		vec![Box::new(textured), Box::new(colored), Box::new(axes)],
+		None,
+	);
+
+	renderer.camera.camera_mat =
+		Mat4::look_at_lh(Vec3::new(1.2, 1.3, -2.0), Vec3::ZERO, Vec3::Y);
+
+	assert!(renderer.mesh::<TexturedShader>(0).is_some());
+	assert!(renderer.mesh::<TexturedShader>(1).is_none());
+
+	assert_golden("mixed_shaders", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+//Both ways of getting a tex coord across a triangle, so one shader can show the difference
+#[derive(Clone, Copy, Debug, Interpolate)]
+struct CheckerCoords {
+	correct : Vec2,
+	#[noperspective]
+	affine : Vec2,
+}
+
+#[derive(Clone, Copy, Debug, Interpolate)]
+struct QualifierP {
+	coords : CheckerCoords,
+	#[flat]
+	tint : Vec3,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct QualifierV {
+	position : Vec3,
+	tex_coord : Vec2,
+	color : Vec3,
+}
+
+//A checkerboard tinted by the provoking vertex's color, with either perspective correct or
+//screen linear tex coords
+struct QualifierShader {
+	affine : bool,
+}
+
+impl Shader for QualifierShader {
+	type Vertex = QualifierV;
+	type Varying = QualifierP;
+	type Uniforms = BasicUniforms;
+
+	fn uniforms(
+		self: &QualifierShader,
+		mesh : &Mesh<QualifierShader>,
+		renderer : &Renderer,
+	) -> BasicUniforms {
+		BasicUniforms::new(mesh, renderer)
+	}
+
+	fn transform_vertex(
+		self: &QualifierShader,
+		vertex : &QualifierV,
+		uniforms : &BasicUniforms,
+	) -> VertTransOut<QualifierP> {
+		VertTransOut {
+			pos : uniforms.clip_pos(vertex.position),
+			varying : QualifierP {
+				coords : CheckerCoords {
+					correct : vertex.tex_coord,
+					affine : vertex.tex_coord,
+				},
+				tint : vertex.color,
+			},
+		}
+	}
+
+	fn color_pixel(
+		self: &QualifierShader,
+		varying : &QualifierP,
+		_derivs : &Derivatives<QualifierP>,
+		_uniforms : &BasicUniforms,
+	) -> Pixel {
+		let coords : Vec2 = if self.affine {
+			varying.coords.affine
+		} else {
+			varying.coords.correct
+		};
+
+		let cell : Vec2 = coords.floor();
+
+		if (cell.x + cell.y).rem_euclid(2_f32) == 0_f32 {
+			varying.tint.extend(1_f32)
+		} else {
+			Pixel::W
+		}
+	}
+}
+
+//Two halves of a floor going off into the distance, perspective correct on the left and affine
+//on the right. Every triangle's first vertex has its own color, which should cover all of it
+#[test]
+fn interpolation_qualifiers() {
+	let floor = |min_x : f32, colors : [Vec3; 2], affine : bool| -> Box<dyn Drawable> {
+		let corner = |x : f32, z : f32, color : Vec3| -> QualifierV {
+			QualifierV {
+				position : Vec3::new(min_x + x, 0.0, z),
+				tex_coord : Vec2::new(x, z),
+				color,
+			}
+		};
+
+		Box::new(Mesh::new(
+			Geometry::Triangles(vec![
+				Triangle::new(
+					corner(0.0, -1.0, colors[0]),
+					corner(4.0, 20.0, Vec3::ZERO),
+					corner(0.0, 20.0, Vec3::ZERO),
+				),
+				Triangle::new(
+					corner(4.0, 20.0, colors[1]),
+					corner(0.0, -1.0, Vec3::ZERO),
+					corner(4.0, -1.0, Vec3::ZERO),
+				),
+			]),
+			QualifierShader { affine },
+			Mat4::IDENTITY,
+		))
+	};
+
+	let mut renderer : Renderer = Renderer::new(
+		RendererSettings::default(),
+		vec![
+			floor(-4.0, [Vec3::X, Vec3::Y], false),
+			floor(0.0, [Vec3::Z, Vec3::new(1.0, 1.0, 0.0)], true),
+		],
+		None,
+	);
+
+	renderer.camera.camera_mat =
+		Mat4::look_at_lh(Vec3::new(0.0, 1.0, -2.0), Vec3::new(0.0, 0.0, 6.0), Vec3::Y);
+
+	assert_golden(
+		"interpolation_qualifiers",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TerrainV {
+	position : Vec3,
+	material : u32,
+	shade : f32,
+}
+
+//Integers always come from the provoking vertex, so material doesn't need marking
+#[derive(Clone, Copy, Debug, Interpolate)]
+struct TerrainP {
+	material : u32,
+	#[flat]
+	shade : f32,
+}
+
+//Low poly look, every triangle is one color
+struct TerrainShader;
+
+const TERRAIN_PALETTE : [Vec3; 4] = [
+	Vec3::new(0.1, 0.3, 0.8),
+	Vec3::new(0.2, 0.7, 0.2),
+	Vec3::new(0.5, 0.4, 0.3),
+	Vec3::new(1.0, 1.0, 1.0),
+];
+
+impl Shader for TerrainShader {
+	type Vertex = TerrainV;
+	type Varying = TerrainP;
+	type Uniforms = BasicUniforms;
+
+	fn uniforms(
+		self: &TerrainShader,
+		mesh : &Mesh<TerrainShader>,
+		renderer : &Renderer,
+	) -> BasicUniforms {
+		BasicUniforms::new(mesh, renderer)
+	}
+
+	fn transform_vertex(
+		self: &TerrainShader,
+		vertex : &TerrainV,
+		uniforms : &BasicUniforms,
+	) -> VertTransOut<TerrainP> {
+		VertTransOut {
+			pos : uniforms.clip_pos(vertex.position),
+			varying : TerrainP {
+				material : vertex.material,
+				shade : vertex.shade,
+			},
+		}
+	}
+
+	fn color_pixel(
+		self: &TerrainShader,
+		varying : &TerrainP,
+		_derivs : &Derivatives<TerrainP>,
+		_uniforms : &BasicUniforms,
+	) -> Pixel {
+		(TERRAIN_PALETTE[varying.material as usize] * varying.shade).extend(1_f32)
+	}
+}
+
+//Hills on a grid of shared vertices, starting behind the camera so the near plane cuts through
+//the closest row of triangles
+fn terrain_renderer(provoking_vertex : ProvokingVertex) -> Renderer {
+	const SIZE : u32 = 12;
+
+	let vertices : Vec<TerrainV> = (0..=SIZE)
+		.flat_map(|z : u32| -> Vec<TerrainV> {
+			(0..=SIZE)
+				.map(|x : u32| -> TerrainV {
+					let (fx, fz) : (f32, f32) = (x as f32 - 6.0, z as f32 - 3.0);
+					let height : f32 = (fx * 0.9).sin() * (fz * 0.7).cos() * 0.8 + fz * 0.12;
+
+					TerrainV {
+						position : Vec3::new(fx, height, fz),
+						material : ((height + 0.6) * 2.0).clamp(0.0, 3.0) as u32,
+						shade : 0.55 + ((x * 7 + z * 13) % 5) as f32 * 0.1,
+					}
+				})
+				.collect()
+		})
+		.collect();
+
+	let indices : Vec<[u32; 3]> = (0..SIZE)
+		.flat_map(|z : u32| -> Vec<[u32; 3]> {
+			(0..SIZE)
+				.flat_map(|x : u32| -> [[u32; 3]; 2] {
+					let corner = |dx : u32, dz : u32| -> u32 { (z + dz) * (SIZE + 1) + x + dx };
+
+					[
+						[corner(0, 0), corner(0, 1), corner(1, 1)],
+						[corner(1, 1), corner(1, 0), corner(0, 0)],
+					]
+				})
+				.collect()
+		})
+		.collect();
+
+	let mut renderer : Renderer = basic_renderer(
+		Mesh::new(
+			Geometry::Indexed {
+				vertices,
+				indices,
+			},
+			TerrainShader,
+			Mat4::IDENTITY,
+		),
+		Mat4::look_at_lh(Vec3::new(0.0, 1.5, -1.0), Vec3::new(0.0, 0.0, 6.0), Vec3::Y),
+	);
+
+	renderer.renderer_settings.provoking_vertex = provoking_vertex;
+
+	renderer
+}
+
+#[test]
+fn flat_terrain() {
+	let mut renderer : Renderer = terrain_renderer(ProvokingVertex::First);
+
+	assert_golden("flat_terrain", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+#[test]
+fn flat_terrain_provoking_last() {
+	let mut renderer : Renderer = terrain_renderer(ProvokingVertex::Last);
+
+	assert_golden(
+		"flat_terrain_provoking_last",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[derive(Clone, Copy, Debug, Interpolate)]
+struct FlatColorP {
+	#[flat]
+	color : Vec3,
+}
+
+//Clip space positions like ClipSpaceShader, but the color is #[flat]
+struct FlatColorShader;
+
+impl Shader for FlatColorShader {
+	type Vertex = BasicV;
+	type Varying = FlatColorP;
+	type Uniforms = ();
+
+	fn uniforms(
+		self: &FlatColorShader,
+		_mesh : &Mesh<FlatColorShader>,
+		_renderer : &Renderer,
+	) -> () {
+	}
+
+	fn transform_vertex(
+		self: &FlatColorShader,
+		vertex : &BasicV,
+		_uniforms : &(),
+	) -> VertTransOut<FlatColorP> {
+		VertTransOut {
+			pos : vertex.position.extend(1.0),
+			varying : FlatColorP {
+				color : vertex.color,
+			},
+		}
+	}
+
+	fn color_pixel(
+		self: &FlatColorShader,
+		varying : &FlatColorP,
+		_derivs : &Derivatives<FlatColorP>,
+		_uniforms : &(),
+	) -> Pixel {
+		varying.color.extend(1.0)
+	}
+}
+
+//A triangle with a different color at each corner, drawn with its wireframe over it. The fill and
+//every edge and corner should all take the provoking vertex's color, the edges inverted
+fn flat_wireframe_colors(provoking_vertex : ProvokingVertex) -> HashSet<Rgb<u8>> {
+	let corner = |x : f32, y : f32, color : Vec3| -> BasicV {
+		BasicV {
+			position : Vec3::new(x, y, 0.5),
+			color,
+		}
+	};
+
+	let mut tri : Mesh<FlatColorShader> = Mesh::new(
+		Geometry::Triangles(vec![Triangle::new(
+			corner(-0.5, -0.5, Vec3::X),
+			corner(0.0, 0.5, Vec3::Y),
+			corner(0.5, -0.5, Vec3::Z),
+		)]),
+		FlatColorShader,
+		Mat4::IDENTITY,
+	);
+
+	tri.polygon_mode = PolygonMode::FillWireframe;
+	tri.line_width = 3.0;
+
+	let mut renderer : Renderer = basic_renderer(tri, Mat4::IDENTITY);
+
+	renderer.renderer_settings.provoking_vertex = provoking_vertex;
+
+	let image : RgbImage = render_scene(&mut renderer);
+	let background : Rgb<u8> = *image.get_pixel(0, 0);
+
+	image.pixels().copied().filter(|p : &Rgb<u8>| -> bool { *p != background }).collect()
+}
+
+#[test]
+fn flat_wireframe_provoking_first() {
+	assert_eq!(
+		flat_wireframe_colors(ProvokingVertex::First),
+		HashSet::from([Rgb([255, 0, 0]), Rgb([0, 255, 255])]),
+		"Only the red fill and its cyan edges should be drawn"
+	);
+}
+
+#[test]
+fn flat_wireframe_provoking_last() {
+	assert_eq!(
+		flat_wireframe_colors(ProvokingVertex::Last),
+		HashSet::from([Rgb([0, 0, 255]), Rgb([255, 255, 0])]),
+		"Only the blue fill and its yellow edges should be drawn"
+	);
+}
+
+//One of each kind of light around a cube, the spot light only catching the front face and the
+//point light giving the right face a highlight
+fn lit_cube_renderer(specular_model : SpecularModel) -> Renderer {
+	let mut cube : Mesh<LitShader> = Mesh::<LitShader>::lit_unit_cube();
+
+	cube.shader.specular_model = specular_model;
+	cube.shader.specular = Vec3::ONE;
+	cube.shader.shininess = 16_f32;
+	cube.cull_mode = CullMode::Back;
+
+	let mut renderer : Renderer = basic_renderer(
+		cube,
+		Mat4::look_at_lh(Vec3::new(1.2, 1.3, -2.0), Vec3::ZERO, Vec3::Y),
+	);
+
+	renderer.lights = vec![
+		Light::Directional {
+			direction : Vec3::new(0.3, -1.0, 0.5),
+			color : Vec3::splat(0.5),
+		},
+		//Placed to bounce off the right face towards the camera, for a highlight
+		Light::Point {
+			position : Vec3::new(1.1, -1.0, 1.6),
+			color : Vec3::new(0.7, 0.8, 1.0),
+			attenuation : Attenuation::default(),
+		},
+		Light::Spot {
+			position : Vec3::new(-0.5, 0.8, -2.0),
+			direction : Vec3::new(0.3, -0.4, 1.0),
+			color : Vec3::splat(0.9),
+			attenuation : Attenuation::NONE,
+			inner_angle : 8_f32.to_radians(),
+			outer_angle : 14_f32.to_radians(),
+		},
+	];
+
+	renderer
+}
+
+#[test]
+fn lit_cube_blinn_phong() {
+	let mut renderer : Renderer = lit_cube_renderer(SpecularModel::BlinnPhong);
+
+	assert_golden(
+		"lit_cube_blinn_phong",
+		&render_scene(&mut renderer),
+		tolerance(DEFAULT_TOLERANCE),
+	);
+}
+
+#[test]
+fn lit_cube_phong() {
+	let mut renderer : Renderer = lit_cube_renderer(SpecularModel::Phong);
+
+	assert_golden("lit_cube_phong", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+//A floor with a point light fading off on the left and a spot light's soft edged cone on the
+//right, with no other light so the falloff is easy to see
+#[test]
+fn lit_floor() {
+	let corner = |x : f32, z : f32| -> LitV {
+		LitV {
+			position : Vec3::new(x, 0.0, z),
+			normal : Vec3::Y,
+			color : Vec3::ONE,
+		}
+	};
+
+	let floor : Mesh<LitShader> = Mesh::new(
+		Geometry::Triangles(vec![
+			Triangle::new(corner(-4.0, -1.0), corner(4.0, 8.0), corner(-4.0, 8.0)),
+			Triangle::new(corner(-4.0, -1.0), corner(4.0, -1.0), corner(4.0, 8.0)),
+		]),
+		LitShader {
+			ambient : Vec3::ZERO,
+			specular : Vec3::ZERO,
+			..LitShader::default()
+		},
+		Mat4::IDENTITY,
+	);
+
+	let mut renderer : Renderer = basic_renderer(
+		floor,
+		Mat4::look_at_lh(Vec3::new(0.0, 2.0, -1.5), Vec3::new(0.0, 0.0, 3.0), Vec3::Y),
+	);
+
+	renderer.lights = vec![
+		Light::Point {
+			position : Vec3::new(-1.5, 0.5, 2.0),
+			color : Vec3::new(1.0, 0.6, 0.2),
+			attenuation : Attenuation {
+				constant : 1.0,
+				linear : 0.5,
+				quadratic : 1.0,
+			},
+		},
+		Light::Spot {
+			position : Vec3::new(1.5, 2.0, 2.0),
+			direction : Vec3::new(0.0, -1.0, 0.3),
+			color : Vec3::new(0.3, 0.6, 1.0),
+			attenuation : Attenuation::default(),
+			inner_angle : 15_f32.to_radians(),
+			outer_angle : 25_f32.to_radians(),
+		},
+	];
+
+	assert_golden("lit_floor", &render_scene(&mut renderer), tolerance(DEFAULT_TOLERANCE));
+}
+
+//Lights go through the uniforms every frame, so ones the update function adds light the cube
+//straight away
+#[test]
+fn lights_set_per_frame() {
+	let center_pixel = |update_fn : Option<UpdateFunc>| -> Rgb<u8> {
+		let mut renderer : Renderer = Renderer::new(
+			RendererSettings::default(),
+			vec![Box::new(Mesh::<LitShader>::lit_unit_cube())],
+			update_fn,
+		);
+
+		renderer.camera.camera_mat =
+			Mat4::look_at_lh(Vec3::new(0.0, 0.0, -2.0), Vec3::ZERO, Vec3::Y);
+
+		let image : RgbImage = render_scene(&mut renderer);
+
+		*image.get_pixel(image.width() / 2, image.height() / 2)
+	};
+
+	let unlit : Rgb<u8> = center_pixel(None);
+	let lit : Rgb<u8> = center_pixel(Some(Box::new(|r : &mut Renderer| -> () {
+		r.lights = vec![Light::Directional {
+			direction : Vec3::Z,
+			color : Vec3::ONE,
+		}];
+	})));
+
+	assert!(
+		lit.0.iter().zip(unlit.0.iter()).all(|(l, u) : (&u8, &u8)| -> bool { l > u }),
+		"{:?} should be brighter than {:?}",
+		lit,
+		unlit
+	);
+}
@@ -0,0 +1,64 @@
+// Render target that doesn't need a
+// window or a display, every frame is
+// written out to a PNG file instead.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use image::{Rgb, RgbImage};
+
+use crate::render_target::{pixel_to_rgb8, Frame, RenderTarget};
+
+pub struct ImageRenderTarget {
+	//Directory the frames are written into, it's created if it doesn't exist yet
+	output_dir : PathBuf,
+	//Index of the next frame to be presented, it ends up in the file name
+	frame_idx : u32,
+}
+
+impl ImageRenderTarget {
+	pub fn new(output_dir : &Path) -> Result<ImageRenderTarget, String> {
+		fs::create_dir_all(output_dir).map_err(|e : std::io::Error| -> String {
+			format!("Could not create {}: {}", output_dir.display(), e)
+		})?;
+
+		Ok(ImageRenderTarget {
+			output_dir : output_dir.to_path_buf(),
+			frame_idx : 0,
+		})
+	}
+
+	//Frames are zero padded so they sort properly and can be fed straight into ffmpeg
+	pub fn frame_path(
+		self: &ImageRenderTarget,
+		frame_idx : u32,
+	) -> PathBuf {
+		self.output_dir.join(format!("frame_{:05}.png", frame_idx))
+	}
+}
+
+impl RenderTarget for ImageRenderTarget {
+	fn present(
+		self: &mut ImageRenderTarget,
+		frame : &Frame,
+	) -> Result<(), String> {
+		let path : PathBuf = self.frame_path(self.frame_idx);
+
+		frame_to_image(frame)
+			.save(&path)
+			.map_err(|e : image::ImageError| -> String {
+				format!("Could not write {}: {}", path.display(), e)
+			})?;
+
+		self.frame_idx += 1;
+
+		Ok(())
+	}
+}
+
+//Alpha is dropped, the same as when presenting to a window
+pub fn frame_to_image(frame : &Frame) -> RgbImage {
+	RgbImage::from_fn(frame.width, frame.height, |x : u32, y : u32| -> Rgb<u8> {
+		Rgb(pixel_to_rgb8(&frame.pixel(x, y)))
+	})
+}
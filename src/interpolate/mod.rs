@@ -0,0 +1,261 @@
+// Blending the varyings shaders pass
+// along between the vertices of whatever
+// primitive they're on.
+
+use std::ops::Sub;
+
+use glam::{Vec2, Vec3, Vec4};
+
+pub use mvevgrs_derive::Interpolate;
+
+//How much each of a primitive's vertices counts towards a value, for each way a value can be
+//interpolated. Lines and clipping only use the first two vertices. They don't have to add up to
+//1, differences between weights are used to get the Derivatives of a varying
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+	//Perspective correct barycentrics, what fields get by default
+	pub perspective : Vec3,
+	//Barycentrics in screen space, for #[noperspective] fields
+	pub linear : Vec3,
+	//Picks out the provoking vertex, for #[flat] fields
+	pub flat : Vec3,
+}
+
+impl Weights {
+	pub const ZERO : Weights = Weights {
+		perspective : Vec3::ZERO,
+		linear : Vec3::ZERO,
+		flat : Vec3::ZERO,
+	};
+
+	//The same weights for a field marked #[noperspective], which passes them on to everything
+	//inside of it
+	pub fn noperspective(self: &Weights) -> Weights {
+		Weights {
+			perspective : self.linear,
+			..*self
+		}
+	}
+
+	//Likewise for #[flat]
+	pub fn flat(self: &Weights) -> Weights {
+		Weights {
+			perspective : self.flat,
+			linear : self.flat,
+			flat : self.flat,
+		}
+	}
+}
+
+//How much the weights change between two spots, interpolating with it gives how much a varying
+//changes between them
+impl Sub for Weights {
+	type Output = Weights;
+
+	fn sub(
+		self: Weights,
+		rhs : Weights,
+	) -> Weights {
+		Weights {
+			perspective : self.perspective - rhs.perspective,
+			linear : self.linear - rhs.linear,
+			flat : self.flat - rhs.flat,
+		}
+	}
+}
+
+//Anything that can be passed from the vertices to the pixels of a primitive. Structs of these
+//can #[derive(Interpolate)]
+pub trait Interpolate : Clone + Copy + Send + Sync {
+	fn interpolate(
+		verts : [&Self; 3],
+		weights : &Weights,
+	) -> Self;
+}
+
+impl Interpolate for f32 {
+	fn interpolate(
+		verts : [&f32; 3],
+		weights : &Weights,
+	) -> f32 {
+		Vec3::new(*verts[0], *verts[1], *verts[2]).dot(weights.perspective)
+	}
+}
+
+impl Interpolate for Vec2 {
+	fn interpolate(
+		verts : [&Vec2; 3],
+		weights : &Weights,
+	) -> Vec2 {
+		*verts[0] * weights.perspective.x
+			+ *verts[1] * weights.perspective.y
+			+ *verts[2] * weights.perspective.z
+	}
+}
+
+impl Interpolate for Vec3 {
+	fn interpolate(
+		verts : [&Vec3; 3],
+		weights : &Weights,
+	) -> Vec3 {
+		*verts[0] * weights.perspective.x
+			+ *verts[1] * weights.perspective.y
+			+ *verts[2] * weights.perspective.z
+	}
+}
+
+impl Interpolate for Vec4 {
+	fn interpolate(
+		verts : [&Vec4; 3],
+		weights : &Weights,
+	) -> Vec4 {
+		*verts[0] * weights.perspective.x
+			+ *verts[1] * weights.perspective.y
+			+ *verts[2] * weights.perspective.z
+	}
+}
+
+//Integers can't be blended between vertices, so like on GPUs (where they have to be marked flat)
+//they always come from the provoking vertex, whatever they're marked as. Done in f64 so every
+//u32 and i32 makes it through exactly
+impl Interpolate for u32 {
+	fn interpolate(
+		verts : [&u32; 3],
+		weights : &Weights,
+	) -> u32 {
+		(*verts[0] as f64 * weights.flat.x as f64
+			+ *verts[1] as f64 * weights.flat.y as f64
+			+ *verts[2] as f64 * weights.flat.z as f64)
+			.round() as u32
+	}
+}
+
+impl Interpolate for i32 {
+	fn interpolate(
+		verts : [&i32; 3],
+		weights : &Weights,
+	) -> i32 {
+		(*verts[0] as f64 * weights.flat.x as f64
+			+ *verts[1] as f64 * weights.flat.y as f64
+			+ *verts[2] as f64 * weights.flat.z as f64)
+			.round() as i32
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use glam::{Vec2, Vec3};
+
+	use super::{Interpolate, Weights};
+
+	#[derive(Clone, Copy, Debug, PartialEq, Interpolate)]
+	struct Named {
+		a : f32,
+		b : Vec2,
+		inner : Tuple<Vec3>,
+	}
+
+	#[derive(Clone, Copy, Debug, PartialEq, Interpolate)]
+	struct Tuple<T>(T, u32);
+
+	#[derive(Clone, Copy, Debug, PartialEq, Interpolate)]
+	struct Unit;
+
+	#[derive(Clone, Copy, Debug, PartialEq, Interpolate)]
+	struct Qualified {
+		smooth : f32,
+		#[noperspective]
+		linear : f32,
+		#[flat]
+		flat : f32,
+		//Qualifiers carry on into the fields inside
+		#[flat]
+		inner : Tuple<Vec2>,
+	}
+
+	const WEIGHTS : Weights = Weights {
+		perspective : Vec3::new(0.5, 0.25, 0.25),
+		linear : Vec3::new(0.25, 0.25, 0.5),
+		flat : Vec3::new(0.0, 1.0, 0.0),
+	};
+
+	fn vert(i : f32) -> Named {
+		Named {
+			a : i,
+			b : Vec2::new(i, i * 2.0),
+			inner : Tuple(Vec3::splat(i * 4.0), i as u32),
+		}
+	}
+
+	//Every field, nested ones included, comes out the same as interpolating it by hand
+	#[test]
+	fn derived_fields() {
+		let verts : [Named; 3] = [vert(1.0), vert(2.0), vert(4.0)];
+		let [v0, v1, v2] : [&Named; 3] = [&verts[0], &verts[1], &verts[2]];
+
+		assert_eq!(
+			Named::interpolate([v0, v1, v2], &WEIGHTS),
+			Named {
+				a : f32::interpolate([&v0.a, &v1.a, &v2.a], &WEIGHTS),
+				b : Vec2::interpolate([&v0.b, &v1.b, &v2.b], &WEIGHTS),
+				inner : Tuple(
+					Vec3::interpolate([&v0.inner.0, &v1.inner.0, &v2.inner.0], &WEIGHTS),
+					u32::interpolate([&v0.inner.1, &v1.inner.1, &v2.inner.1], &WEIGHTS),
+				),
+			}
+		);
+		assert_eq!(
+			Named::interpolate([v0, v1, v2], &WEIGHTS),
+			Named {
+				a : 2.0,
+				b : Vec2::new(2.0, 4.0),
+				inner : Tuple(Vec3::splat(8.0), 2),
+			}
+		);
+		assert_eq!(Unit::interpolate([&Unit, &Unit, &Unit], &WEIGHTS), Unit);
+	}
+
+	#[test]
+	fn qualified_weights() {
+		assert_eq!(
+			WEIGHTS.noperspective(),
+			Weights {
+				perspective : WEIGHTS.linear,
+				..WEIGHTS
+			}
+		);
+		assert_eq!(
+			WEIGHTS.flat(),
+			Weights {
+				perspective : WEIGHTS.flat,
+				linear : WEIGHTS.flat,
+				flat : WEIGHTS.flat,
+			}
+		);
+		//Flat wins over noperspective when a field ends up with both
+		assert_eq!(WEIGHTS.noperspective().flat(), WEIGHTS.flat());
+		assert_eq!(WEIGHTS.flat().noperspective(), WEIGHTS.flat());
+	}
+
+	#[test]
+	fn derived_qualifiers() {
+		let vert = |i : f32| -> Qualified {
+			Qualified {
+				smooth : i,
+				linear : i,
+				flat : i,
+				inner : Tuple(Vec2::splat(i), i as u32),
+			}
+		};
+
+		assert_eq!(
+			Qualified::interpolate([&vert(1.0), &vert(2.0), &vert(8.0)], &WEIGHTS),
+			Qualified {
+				smooth : 3.0,
+				linear : 4.75,
+				flat : 2.0,
+				inner : Tuple(Vec2::splat(2.0), 2),
+			}
+		);
+	}
+}
@@ -0,0 +1,24 @@
+// A software rasterizer, everything the
+// demo in main.rs is built out of is in
+// here for other programs to use too.
+
+//Lets #[derive(Interpolate)] name things by the path other crates use
+extern crate self as mvevgrs;
+
+pub mod renderer;
+pub mod pixel;
+pub mod mesh;
+pub mod shader;
+pub mod interpolate;
+pub mod lighting;
+pub mod texture;
+pub mod blend;
+pub mod post_process;
+pub mod render_target;
+pub mod window_render_target;
+pub mod image_render_target;
+pub mod capture_render_target;
+pub mod video_render_target;
+pub mod obj;
+#[cfg(test)]
+mod golden_tests;
@@ -0,0 +1,360 @@
+// Lights and the shader that lights meshes
+// with them, Phong or Blinn-Phong shaded
+// per pixel.
+
+use glam::{Mat3, Mat4, Vec3};
+
+use crate::interpolate::Interpolate;
+use crate::mesh::{Derivatives, Mesh, VertTransOut};
+use crate::pixel::Pixel;
+use crate::renderer::Renderer;
+use crate::shader::{BasicUniforms, Shader};
+
+//Something that lights up a scene. Everything is in world space, and color is how bright the
+//light is as well as what color
+#[derive(Clone, Copy, Debug)]
+pub enum Light {
+	//Infinitely far away like the sun, direction is which way the light travels
+	Directional {
+		direction : Vec3,
+		color : Vec3,
+	},
+	//Shines every way from one spot, getting dimmer further away
+	Point {
+		position : Vec3,
+		color : Vec3,
+		attenuation : Attenuation,
+	},
+	//A point light that only shines in a cone around direction. The angles are in radians from
+	//the middle of the cone, full brightness inside inner_angle fading out to nothing at
+	//outer_angle
+	Spot {
+		position : Vec3,
+		direction : Vec3,
+		color : Vec3,
+		attenuation : Attenuation,
+		inner_angle : f32,
+		outer_angle : f32,
+	},
+}
+
+impl Light {
+	// Which way a point has to look to see
+	// the light and how much of it reaches
+	// the point, None if none of it does.
+	// Points right on a light get a zero
+	// direction, so it doesn't light them
+	pub fn incoming(
+		self: &Light,
+		point : Vec3,
+	) -> Option<(Vec3, Vec3)> {
+		match *self {
+			Light::Directional {
+				direction,
+				color,
+			} => Some((-direction.normalize_or_zero(), color)),
+			Light::Point {
+				position,
+				color,
+				attenuation,
+			} => {
+				let to_light : Vec3 = position - point;
+
+				Some((
+					to_light.normalize_or_zero(),
+					color * attenuation.factor(to_light.length()),
+				))
+			},
+			Light::Spot {
+				position,
+				direction,
+				color,
+				attenuation,
+				inner_angle,
+				outer_angle,
+			} => {
+				let to_light : Vec3 = position - point;
+				let l : Vec3 = to_light.normalize_or_zero();
+
+				//Cosines get bigger closer to the middle of the cone
+				let cos_angle : f32 = (-l).dot(direction.normalize_or_zero());
+				let (cos_inner, cos_outer) : (f32, f32) = (inner_angle.cos(), outer_angle.cos());
+
+				if cos_angle <= cos_outer {
+					return None;
+				}
+
+				//Smoothstep so the edge of the cone doesn't end in a hard line
+				let t : f32 = ((cos_angle - cos_outer) / (cos_inner - cos_outer).max(f32::EPSILON))
+					.clamp(0_f32, 1_f32);
+				let cone : f32 = t * t * (3_f32 - 2_f32 * t);
+
+				Some((l, color * attenuation.factor(to_light.length()) * cone))
+			},
+		}
+	}
+}
+
+//How a point or spot light falls off with distance, 1 / (constant + linear * d + quadratic * d^2)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Attenuation {
+	pub constant : f32,
+	pub linear : f32,
+	pub quadratic : f32,
+}
+
+impl Attenuation {
+	//Lights that are just as bright however far away they are
+	pub const NONE : Attenuation = Attenuation {
+		constant : 1_f32,
+		linear : 0_f32,
+		quadratic : 0_f32,
+	};
+
+	//Nothing gets through if the falloff comes out at 0 or less, rather than dividing by it
+	pub fn factor(
+		self: &Attenuation,
+		distance : f32,
+	) -> f32 {
+		let falloff : f32 =
+			self.constant + self.linear * distance + self.quadratic * distance * distance;
+
+		if falloff > 0_f32 {
+			1_f32 / falloff
+		} else {
+			0_f32
+		}
+	}
+}
+
+//Falls off to about a third by 10 units away
+impl Default for Attenuation {
+	fn default() -> Attenuation {
+		Attenuation {
+			constant : 1_f32,
+			linear : 0.09,
+			quadratic : 0.032,
+		}
+	}
+}
+
+//How the shiny highlight is worked out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecularModel {
+	//Angle between the view direction and the light reflected off the surface
+	Phong,
+	//Angle between the normal and the halfway vector between the light and view directions.
+	//Cheaper, and highlights don't get cut off at grazing angles
+	BlinnPhong,
+}
+
+//Lights a mesh per pixel with every one of the renderer's lights. The vertex color is what the
+//surface reflects of the ambient and diffuse light, and specular is the color of its highlights
+#[derive(Clone, Copy, Debug)]
+pub struct LitShader {
+	//Light that reaches everything from everywhere, so the sides facing away from every light
+	//aren't pitch black
+	pub ambient : Vec3,
+	pub specular : Vec3,
+	//Higher makes for smaller, sharper highlights
+	pub shininess : f32,
+	pub specular_model : SpecularModel,
+}
+
+impl Default for LitShader {
+	fn default() -> LitShader {
+		LitShader {
+			ambient : Vec3::splat(0.1),
+			specular : Vec3::splat(0.5),
+			shininess : 32_f32,
+			specular_model : SpecularModel::BlinnPhong,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LitV {
+	pub position : Vec3,
+	pub normal : Vec3,
+	pub color : Vec3,
+}
+
+//Lighting is worked out in world space, so that's where positions and normals are passed along
+#[derive(Clone, Copy, Debug, Interpolate)]
+pub struct LitP {
+	pub world_pos : Vec3,
+	pub normal : Vec3,
+	pub color : Vec3,
+}
+
+#[derive(Clone, Debug)]
+pub struct LitUniforms {
+	pub basic : BasicUniforms,
+	pub model_mat : Mat4,
+	//Takes normals into world space, unlike the model matrix this keeps them at right angles to
+	//the surface when it's scaled unevenly
+	pub normal_mat : Mat3,
+	pub camera_pos : Vec3,
+	//The renderer's lights as they are this frame
+	pub lights : Vec<Light>,
+}
+
+impl Shader for LitShader {
+	type Vertex = LitV;
+	type Varying = LitP;
+	type Uniforms = LitUniforms;
+
+	fn uniforms(
+		self: &LitShader,
+		mesh : &Mesh<LitShader>,
+		renderer : &Renderer,
+	) -> LitUniforms {
+		LitUniforms {
+			basic : BasicUniforms::new(mesh, renderer),
+			model_mat : mesh.model_mat,
+			normal_mat : Mat3::from_mat4(mesh.model_mat).inverse().transpose(),
+			//The camera matrix takes the world to the camera, so its inverse puts the camera
+			//back in the world
+			camera_pos : renderer.camera.camera_mat.inverse().transform_point3(Vec3::ZERO),
+			lights : renderer.lights.clone(),
+		}
+	}
+
+	fn transform_vertex(
+		self: &LitShader,
+		vertex : &LitV,
+		uniforms : &LitUniforms,
+	) -> VertTransOut<LitP> {
+		VertTransOut {
+			pos : uniforms.basic.clip_pos(vertex.position),
+			varying : LitP {
+				world_pos : uniforms.model_mat.transform_point3(vertex.position),
+				normal : uniforms.normal_mat * vertex.normal,
+				color : vertex.color,
+			},
+		}
+	}
+
+	fn color_pixel(
+		self: &LitShader,
+		varying : &LitP,
+		_derivs : &Derivatives<LitP>,
+		uniforms : &LitUniforms,
+	) -> Pixel {
+		//Interpolated normals are a bit shorter than 1 between the vertices
+		let n : Vec3 = varying.normal.normalize_or_zero();
+		let v : Vec3 = (uniforms.camera_pos - varying.world_pos).normalize_or_zero();
+
+		let lit : Vec3 = uniforms.lights.iter().fold(
+			self.ambient * varying.color,
+			|lit : Vec3, light : &Light| -> Vec3 {
+				let Some((l, radiance)) = light.incoming(varying.world_pos) else {
+					return lit;
+				};
+
+				let diffuse : f32 = n.dot(l);
+
+				//Facing away from the light, so no highlight either
+				if diffuse <= 0_f32 {
+					return lit;
+				}
+
+				let specular : f32 = match self.specular_model {
+					SpecularModel::Phong => (-l).reflect(n).dot(v),
+					SpecularModel::BlinnPhong => n.dot((l + v).normalize_or_zero()),
+				}
+				.max(0_f32)
+				.powf(self.shininess);
+
+				lit + radiance * (varying.color * diffuse + self.specular * specular)
+			},
+		);
+
+		lit.extend(1_f32)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use glam::Vec3;
+
+	use super::{Attenuation, Light};
+
+	//Shading a point right where a light is has to come out dark rather than NaN
+	#[test]
+	fn light_at_shaded_point() {
+		let lights : [Light; 2] = [
+			Light::Point {
+				position : Vec3::ONE,
+				color : Vec3::ONE,
+				attenuation : Attenuation {
+					constant : 0.0,
+					linear : 0.0,
+					quadratic : 1.0,
+				},
+			},
+			Light::Spot {
+				position : Vec3::ONE,
+				direction : Vec3::NEG_Y,
+				color : Vec3::ONE,
+				attenuation : Attenuation::default(),
+				inner_angle : 1.0,
+				outer_angle : 2.0,
+			},
+		];
+
+		for light in lights {
+			let (l, radiance) : (Vec3, Vec3) =
+				light.incoming(Vec3::ONE).expect("The light should reach the point");
+
+			assert_eq!(l, Vec3::ZERO);
+			assert!(radiance.is_finite(), "{:?} gave {}", light, radiance);
+		}
+	}
+
+	#[test]
+	fn attenuation_factor() {
+		let attenuation : Attenuation = Attenuation {
+			constant : 1.0,
+			linear : 0.5,
+			quadratic : 0.25,
+		};
+
+		assert_eq!(attenuation.factor(0.0), 1.0);
+		assert_eq!(attenuation.factor(2.0), 1.0 / 3.0);
+		assert_eq!(Attenuation::NONE.factor(100.0), 1.0);
+		assert_eq!(
+			Attenuation {
+				constant : 0.0,
+				linear : 0.0,
+				quadratic : 0.0,
+			}
+			.factor(1.0),
+			0.0
+		);
+	}
+
+	//Full brightness inside the inner angle, nothing past the outer one
+	#[test]
+	fn spot_cone() {
+		let spot : Light = Light::Spot {
+			position : Vec3::ZERO,
+			direction : Vec3::Z,
+			color : Vec3::ONE,
+			attenuation : Attenuation::NONE,
+			inner_angle : 10_f32.to_radians(),
+			outer_angle : 20_f32.to_radians(),
+		};
+
+		let at_angle = |degrees : f32| -> Option<Vec3> {
+			let (sin, cos) : (f32, f32) = degrees.to_radians().sin_cos();
+
+			spot.incoming(Vec3::new(sin, 0.0, cos))
+				.map(|(_, radiance) : (Vec3, Vec3)| -> Vec3 { radiance })
+		};
+
+		assert_eq!(at_angle(5.0), Some(Vec3::ONE));
+		assert!(at_angle(15.0).is_some_and(|r : Vec3| -> bool { r.x > 0.0 && r.x < 1.0 }));
+		assert_eq!(at_angle(25.0), None);
+	}
+}
@@ -1,42 +1,39 @@
-mod renderer;
-mod pixel;
-mod mesh;
-mod window_render_target;
-
+use std::num::ParseIntError;
+use std::path::Path;
 use std::time::{Duration, Instant};
 
 use glam::{Mat4, Vec3};
 
-use crate::mesh::{
-	basic_color_env_updater,
-	basic_pixel_colorer,
-	basic_trans_env_updater,
-	basic_vertex_transformer,
-	BasicCE,
-	BasicP,
-	BasicTE,
-	BasicV,
-	Mesh,
-	Triangle,
-};
-use crate::renderer::{Renderer, RendererSettings};
-use crate::window_render_target::WindowRenderTarget;
+use mvevgrs::image_render_target::ImageRenderTarget;
+use mvevgrs::lighting::{Attenuation, Light, LitShader};
+use mvevgrs::mesh::{CullMode, Mesh};
+use mvevgrs::render_target::run_frames;
+use mvevgrs::renderer::{AntiAliasing, Renderer, RendererSettings, SampleCount};
+use mvevgrs::window_render_target::WindowRenderTarget;
 
 fn main() -> Result<(), ()> {
 	let start_time : Instant = Instant::now();
 
 	let mut frame_start_time : Instant = Instant::now();
 
-	let mut last_frame_duration : Duration = Duration::from_secs(0);
-
 	let fps_debug : bool = false;
 
-	let mut renderer : Renderer<BasicV, BasicTE, BasicP, BasicCE> = Renderer::new(
-		RendererSettings::default(),
-		vec![Mesh::<BasicV, BasicTE, BasicP, BasicCE>::unit_cube()],
+	//The cube is closed so the faces pointing away from the camera are always hidden anyway
+	let mut cube : Mesh<LitShader> = Mesh::<LitShader>::lit_unit_cube();
+
+	cube.cull_mode = CullMode::Back;
+
+	let renderer_settings : RendererSettings = RendererSettings {
+		anti_aliasing : AntiAliasing::Msaa(SampleCount::X4),
+		..RendererSettings::default()
+	};
+
+	let mut renderer : Renderer = Renderer::new(
+		renderer_settings,
+		vec![Box::new(cube)],
 		Some(Box::new(
-			move |r : &mut Renderer<BasicV, BasicTE, BasicP, BasicCE>| -> () {
-				last_frame_duration = Instant::now().duration_since(frame_start_time);
+			move |r : &mut Renderer| -> () {
+				let last_frame_duration : Duration = Instant::now().duration_since(frame_start_time);
 				frame_start_time = Instant::now();
 
 				if fps_debug {
@@ -46,17 +43,76 @@ fn main() -> Result<(), ()> {
 				}
 
 				let t : f32 = Instant::now().duration_since(start_time).as_secs_f32();
-				r.meshes[0].model_mat = Mat4::from_translation(Vec3::new(-1.0, -1.0, 1.0))
+				let cube : &mut Mesh<LitShader> =
+					r.mesh_mut(0).expect("The cube should be the first mesh");
+
+				cube.model_mat = Mat4::from_translation(Vec3::new(-1.0, -1.0, 1.0))
 				// * Mat4::from_rotation_x(t)
 				* Mat4::from_rotation_y( t ) //std::f32::consts::PI / 4_f32)
 				// * Mat4::from_rotation_z(t);
 				;
+
+				//The fill light circles the cube the other way
+				if let Some(Light::Point { position, .. }) = r.lights.get_mut(1) {
+					*position = Vec3::new(-1.0, -0.5, 1.0)
+						+ Vec3::new((-t).cos(), 0.0, (-t).sin()) * 2_f32;
+				}
 			},
 		)),
 	);
 
-	WindowRenderTarget::<BasicV, BasicTE, BasicP, BasicCE>::new(&mut renderer)
-		.expect("bruhhh");
+	//A warm key light from above, a blue fill light to the side and a spot light shining from
+	//the camera
+	renderer.lights = vec![
+		Light::Directional {
+			direction : Vec3::new(0.5, -1.0, 0.8),
+			color : Vec3::splat(0.6),
+		},
+		Light::Point {
+			position : Vec3::new(1.0, 0.0, 0.0),
+			color : Vec3::new(0.3, 0.4, 1.0),
+			attenuation : Attenuation::default(),
+		},
+		Light::Spot {
+			position : Vec3::ZERO,
+			direction : Vec3::new(-1.0, -1.0, 1.0),
+			color : Vec3::splat(0.8),
+			attenuation : Attenuation::default(),
+			inner_angle : 10_f32.to_radians(),
+			outer_angle : 20_f32.to_radians(),
+		},
+	];
+
+	//Running with `--headless <frame count> <output dir>` writes frames out as PNGs instead of
+	//opening a window, for machines that don't have a display
+	let args : Vec<String> = std::env::args().collect();
+
+	if args.get(1).map(String::as_str) == Some("--headless") {
+		//Leaving the frame count out draws one frame, but a typo in it shouldn't
+		let parsed : Option<Result<u32, ParseIntError>> =
+			args.get(2).map(|s : &String| -> Result<u32, ParseIntError> { s.parse() });
+
+		let frame_count : u32 = match parsed {
+			None => 1,
+			Some(Ok(frame_count)) => frame_count,
+			Some(Err(_)) => {
+				eprintln!("Usage: {} --headless <frame count> <output dir>", args[0]);
+
+				std::process::exit(2);
+			},
+		};
+
+		let output_dir : &Path = Path::new(args.get(3).map_or("frames", String::as_str));
+
+		let mut target : ImageRenderTarget = ImageRenderTarget::new(output_dir)
+			.expect("Headless rendering should be able to make its output directory");
+
+		run_frames(&mut renderer, &mut target, frame_count)
+			.expect("Headless rendering should be able to write its frames");
+	} else {
+		WindowRenderTarget::new(&mut renderer)
+			.expect("bruhhh");
+	}
 
 	Ok(())
 }
@@ -1,73 +1,261 @@
-use std::ops::{Add, Mul};
+use glam::{Mat4, Vec2, Vec3, Vec4};
 
-use glam::{Mat4, Vec3, Vec4};
+use crate::blend::BlendState;
+use crate::lighting::{LitShader, LitV};
+use crate::shader::{demo_texture, BasicShader, BasicV, Shader, TexturedShader, TexturedV};
 
-use crate::pixel::Pixel;
-use crate::renderer::Renderer;
-
-//TODO: add enum that allows giving mesh simple list of points instead of list of triangles
-
-//A mesh of triangles that have other data. The "V" type is the triangle's vertex data
-//(probably contains a vec3 position. The "TE" type is the vertex transformer enviorment (probably
-//contains projection-camera-model matrix). The "P" type is the pixel coloring data produced by the
-//vertex transform that gets interpolated across the triangle. The "CE" type is the pixel colorer enviorment.
-//Like the "TE" type, it's just information that's computed once per mesh draw and is then available to the
-//vertex transform and pixel coloring functions.
+//A mesh of primitives and the shader that draws them. What the vertices hold and how they get
+//colored is all up to the shader, see ./src/shader/mod.rs
 
 #[derive(Clone)]
-pub struct Mesh<V, TE, P, CE> {
+pub struct Mesh<S : Shader> {
 	//Mesh data that's drawn,
-	pub tris : Vec<Triangle<V>>,
-	pub vertex_transformer : VertexTransformer<V, TE, P, CE>,
-	pub trans_env_updater : VertexEnvUpdater<V, TE, P, CE>,
-	pub pixel_colorer : PixelColorer<V, TE, P, CE>,
-	pub color_env_updater : ColorEnvUpdater<V, TE, P, CE>,
+	pub geometry : Geometry<S::Vertex>,
+	pub shader : S,
 	pub model_mat : Mat4,
+	//Which triangles get thrown away based on which way they face, none by default so double
+	//sided things like foliage just work
+	pub cull_mode : CullMode,
+	//The winding triangles facing the camera have on screen
+	pub front_face : FrontFace,
+	//How the mesh's pixels are tested against and written to the depth buffer
+	pub depth_state : DepthState,
+	//Stencil test and what happens to the stencil buffer, None leaves it alone
+	pub stencil_state : Option<StencilState>,
+	//How the mesh's pixels are mixed with what's under them, None just replaces it. Blended
+	//meshes are drawn after the opaque ones, furthest first
+	pub blend_state : Option<BlendState>,
+	//Whether triangles are filled in or only their edges or corners are drawn
+	pub polygon_mode : PolygonMode,
+	//How many pixels thick lines are, including wireframe edges. Anything under 1 is drawn 1
+	//pixel thick
+	pub line_width : f32,
+	//How many pixels wide the square drawn for each point is, including the ones
+	//PolygonMode::Points draws
+	pub point_size : f32,
 }
 
-impl<V, TE, P, CE> Mesh<V, TE, P, CE> {
+impl<S : Shader> Mesh<S> {
 	pub fn new(
-		tris : Vec<Triangle<V>>,
-		vertex_transformer : VertexTransformer<V, TE, P, CE>,
-		pixel_colorer : PixelColorer<V, TE, P, CE>,
-		trans_env_updater : VertexEnvUpdater<V, TE, P, CE>,
-		color_env_updater : ColorEnvUpdater<V, TE, P, CE>,
+		geometry : Geometry<S::Vertex>,
+		shader : S,
 		model_mat : Mat4,
-	) -> Mesh<V, TE, P, CE> {
+	) -> Mesh<S> {
 		Mesh {
-			tris,
-			vertex_transformer,
-			trans_env_updater,
-			pixel_colorer,
-			color_env_updater,
+			geometry,
+			shader,
 			model_mat,
+			cull_mode : CullMode::None,
+			front_face : FrontFace::CounterClockwise,
+			depth_state : DepthState::default(),
+			stencil_state : None,
+			blend_state : None,
+			polygon_mode : PolygonMode::Fill,
+			line_width : 1_f32,
+			point_size : 1_f32,
+		}
+	}
+
+	//The same mesh drawn with another shader that takes the same vertices
+	pub fn with_shader<T : Shader<Vertex = S::Vertex>>(
+		self: Mesh<S>,
+		shader : T,
+	) -> Mesh<T> {
+		Mesh {
+			geometry : self.geometry,
+			shader,
+			model_mat : self.model_mat,
+			cull_mode : self.cull_mode,
+			front_face : self.front_face,
+			depth_state : self.depth_state,
+			stencil_state : self.stencil_state,
+			blend_state : self.blend_state,
+			polygon_mode : self.polygon_mode,
+			line_width : self.line_width,
+			point_size : self.point_size,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CullMode {
+	None,
+	//Skip triangles facing away from the camera
+	Back,
+	//Skip triangles facing the camera
+	Front,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontFace {
+	CounterClockwise,
+	Clockwise,
+}
+
+//How a mesh's triangles are turned into pixels. Every mode colors its pixels with the mesh's
+//shader, using the varying interpolated along the edge or taken from the vertex.
+//Lines and points are always drawn as they are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolygonMode {
+	Fill,
+	//Lines along the edges of each triangle, line_width pixels thick and depth tested like
+	//everything else
+	Wireframe,
+	//A square point_size pixels wide at each vertex
+	Points,
+	//Filled triangles with their edges drawn on top in inverted colors so they stand out
+	FillWireframe,
+}
+
+impl PolygonMode {
+	pub fn fills(self: PolygonMode) -> bool {
+		matches!(self, PolygonMode::Fill | PolygonMode::FillWireframe)
+	}
+
+	pub fn draws_edges(self: PolygonMode) -> bool {
+		matches!(self, PolygonMode::Wireframe | PolygonMode::FillWireframe)
+	}
+}
+
+//How a new value is compared against the one already in a buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareFunc {
+	Never,
+	Less,
+	LessEqual,
+	Greater,
+	GreaterEqual,
+	Equal,
+	NotEqual,
+	Always,
+}
+
+impl CompareFunc {
+	pub fn passes<T : PartialOrd>(
+		self: CompareFunc,
+		new : T,
+		existing : T,
+	) -> bool {
+		match self {
+			CompareFunc::Never => false,
+			CompareFunc::Less => new < existing,
+			CompareFunc::LessEqual => new <= existing,
+			CompareFunc::Greater => new > existing,
+			CompareFunc::GreaterEqual => new >= existing,
+			CompareFunc::Equal => new == existing,
+			CompareFunc::NotEqual => new != existing,
+			CompareFunc::Always => true,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthState {
+	//A pixel is only drawn if its depth compared to what's in the depth buffer passes this
+	pub compare : CompareFunc,
+	//Whether drawn pixels replace what's in the depth buffer, decals and see through things
+	//usually want this off
+	pub write : bool,
+}
+
+impl Default for DepthState {
+	fn default() -> DepthState {
+		DepthState {
+			compare : CompareFunc::Less,
+			write : true,
 		}
 	}
 }
 
-impl<V, TE, P, CE> Mesh<V, TE, P, CE> {
+//What happens to a pixel's stencil value after the stencil and depth tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StencilOp {
+	Keep,
+	Zero,
+	//Set it to the reference value
+	Replace,
+	//Add or subtract 1, stopping at 0 and 255
+	IncrementClamp,
+	DecrementClamp,
+	//Add or subtract 1, going around past 0 and 255
+	IncrementWrap,
+	DecrementWrap,
+	Invert,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StencilState {
+	//The pixel passes if the reference compared to what's in the stencil buffer passes this,
+	//both masked by read_mask first
+	pub compare : CompareFunc,
+	pub reference : u8,
+	pub read_mask : u8,
+	//Only these bits of the stencil buffer get changed
+	pub write_mask : u8,
+	//Used when the stencil test fails
+	pub fail : StencilOp,
+	//Used when the stencil test passes but the depth test doesn't
+	pub depth_fail : StencilOp,
+	//Used when both pass
+	pub pass : StencilOp,
+}
+
+impl StencilState {
+	pub fn test(
+		self: &StencilState,
+		stencil : u8,
+	) -> bool {
+		self.compare
+			.passes(self.reference & self.read_mask, stencil & self.read_mask)
+	}
+
+	//What the stencil value becomes after op
+	pub fn apply(
+		self: &StencilState,
+		op : StencilOp,
+		stencil : u8,
+	) -> u8 {
+		let new : u8 = match op {
+			StencilOp::Keep => stencil,
+			StencilOp::Zero => 0,
+			StencilOp::Replace => self.reference,
+			StencilOp::IncrementClamp => stencil.saturating_add(1),
+			StencilOp::DecrementClamp => stencil.saturating_sub(1),
+			StencilOp::IncrementWrap => stencil.wrapping_add(1),
+			StencilOp::DecrementWrap => stencil.wrapping_sub(1),
+			StencilOp::Invert => !stencil,
+		};
+
+		(stencil & !self.write_mask) | (new & self.write_mask)
+	}
+}
+
+impl Default for StencilState {
+	//Always passes and never changes anything
+	fn default() -> StencilState {
+		StencilState {
+			compare : CompareFunc::Always,
+			reference : 0,
+			read_mask : 0xff,
+			write_mask : 0xff,
+			fail : StencilOp::Keep,
+			depth_fail : StencilOp::Keep,
+			pass : StencilOp::Keep,
+		}
+	}
+}
+
+impl Mesh<BasicShader> {
 	//A unit cube centered at the origin
-	pub fn unit_cube() -> Mesh<BasicV, BasicTE, BasicP, BasicCE> {
+	pub fn unit_cube() -> Mesh<BasicShader> {
 		Mesh::new(
-			vec![
-				//Front Face
-				//Upper Left
-				Triangle::new(
+			Geometry::Indexed {
+				vertices : vec![
+					//Front Face
 					BasicV {
 						position : Vec3::new(-0.5, -0.5, -0.5),
 						color : Vec3::new(0.0, 0.0, 0.0),
 					},
-					BasicV {
-						position : Vec3::new(0.5, 0.5, -0.5),
-						color : Vec3::new(1.0, 1.0, 0.0),
-					},
-					BasicV {
-						position : Vec3::new(-0.5, 0.5, -0.5),
-						color : Vec3::new(0.0, 1.0, 0.0),
-					},
-				),
-				//Lower Right
-				Triangle::new(
 					BasicV {
 						position : Vec3::new(0.5, -0.5, -0.5),
 						color : Vec3::new(1.0, 0.0, 0.0),
@@ -76,76 +264,11 @@ impl<V, TE, P, CE> Mesh<V, TE, P, CE> {
 						position : Vec3::new(0.5, 0.5, -0.5),
 						color : Vec3::new(1.0, 1.0, 0.0),
 					},
-					BasicV {
-						position : Vec3::new(-0.5, -0.5, -0.5),
-						color : Vec3::new(0.0, 0.0, 0.0),
-					},
-				),
-				//Back Face
-				//Upper Left
-				Triangle::new(
-					BasicV {
-						position : Vec3::new(-0.5, -0.5, 0.5),
-						color : Vec3::new(0.0, 0.0, 1.0),
-					},
-					BasicV {
-						position : Vec3::new(-0.5, 0.5, 0.5),
-						color : Vec3::new(0.0, 1.0, 1.0),
-					},
-					BasicV {
-						position : Vec3::new(0.5, 0.5, 0.5),
-						color : Vec3::new(1.0, 1.0, 1.0),
-					},
-				),
-				//Lower Right
-				Triangle::new(
-					BasicV {
-						position : Vec3::new(0.5, -0.5, 0.5),
-						color : Vec3::new(1.0, 0.0, 1.0),
-					},
-					BasicV {
-						position : Vec3::new(0.5, 0.5, 0.5),
-						color : Vec3::new(1.0, 1.0, 1.0),
-					},
-					BasicV {
-						position : Vec3::new(-0.5, -0.5, 0.5),
-						color : Vec3::new(0.0, 0.0, 1.0),
-					},
-				),
-				//Top Face
-				//Upper Left
-				Triangle::new(
-					BasicV {
-						position : Vec3::new(-0.5, 0.5, 0.5),
-						color : Vec3::new(0.0, 1.0, 1.0),
-					},
-					BasicV {
-						position : Vec3::new(0.5, 0.5, 0.5),
-						color : Vec3::new(1.0, 1.0, 1.0),
-					},
-					BasicV {
-						position : Vec3::new(-0.5, 0.5, -0.5),
-						color : Vec3::new(0.0, 1.0, 0.0),
-					},
-				),
-				//Lower Right
-				Triangle::new(
-					BasicV {
-						position : Vec3::new(0.5, 0.5, 0.5),
-						color : Vec3::new(1.0, 1.0, 1.0),
-					},
-					BasicV {
-						position : Vec3::new(0.5, 0.5, -0.5),
-						color : Vec3::new(1.0, 1.0, 0.0),
-					},
 					BasicV {
 						position : Vec3::new(-0.5, 0.5, -0.5),
 						color : Vec3::new(0.0, 1.0, 0.0),
 					},
-				),
-				//Bottom Face
-				//Upper Left
-				Triangle::new(
+					//Back Face
 					BasicV {
 						position : Vec3::new(-0.5, -0.5, 0.5),
 						color : Vec3::new(0.0, 0.0, 1.0),
@@ -154,102 +277,45 @@ impl<V, TE, P, CE> Mesh<V, TE, P, CE> {
 						position : Vec3::new(0.5, -0.5, 0.5),
 						color : Vec3::new(1.0, 0.0, 1.0),
 					},
-					BasicV {
-						position : Vec3::new(-0.5, -0.5, -0.5),
-						color : Vec3::new(0.0, 0.0, 0.0),
-					},
-				),
-				//Lower Right
-				Triangle::new(
-					BasicV {
-						position : Vec3::new(0.5, -0.5, 0.5),
-						color : Vec3::new(1.0, 0.0, 1.0),
-					},
-					BasicV {
-						position : Vec3::new(0.5, -0.5, -0.5),
-						color : Vec3::new(1.0, 0.0, 0.0),
-					},
-					BasicV {
-						position : Vec3::new(-0.5, -0.5, -0.5),
-						color : Vec3::new(0.0, 0.0, 0.0),
-					},
-				),
-				//Right Face
-				//Upper Left
-				Triangle::new(
-					BasicV {
-						position : Vec3::new(0.5, 0.5, -0.5),
-						color : Vec3::new(1.0, 1.0, 0.0),
-					},
 					BasicV {
 						position : Vec3::new(0.5, 0.5, 0.5),
 						color : Vec3::new(1.0, 1.0, 1.0),
 					},
-					BasicV {
-						position : Vec3::new(0.5, -0.5, -0.5),
-						color : Vec3::new(1.0, 0.0, 0.0),
-					},
-				),
-				//Lower Right
-				Triangle::new(
-					BasicV {
-						position : Vec3::new(0.5, 0.5, 0.5),
-						color : Vec3::new(1.0, 1.0, 1.0),
-					},
-					BasicV {
-						position : Vec3::new(0.5, -0.5, 0.5),
-						color : Vec3::new(1.0, 0.0, 1.0),
-					},
-					BasicV {
-						position : Vec3::new(0.5, -0.5, -0.5),
-						color : Vec3::new(1.0, 0.0, 0.0),
-					},
-				),
-				//Left Face
-				//Upper Left
-				Triangle::new(
 					BasicV {
 						position : Vec3::new(-0.5, 0.5, 0.5),
 						color : Vec3::new(0.0, 1.0, 1.0),
 					},
-					BasicV {
-						position : Vec3::new(-0.5, 0.5, -0.5),
-						color : Vec3::new(0.0, 1.0, 0.0),
-					},
-					BasicV {
-						position : Vec3::new(-0.5, -0.5, -0.5),
-						color : Vec3::new(0.0, 0.0, 0.0),
-					},
-				),
-				//Lower Right
-				Triangle::new(
-					BasicV {
-						position : Vec3::new(-0.5, 0.5, 0.5),
-						color : Vec3::new(0.0, 1.0, 1.0),
-					},
-					BasicV {
-						position : Vec3::new(-0.5, -0.5, 0.5),
-						color : Vec3::new(0.0, 0.0, 1.0),
-					},
-					BasicV {
-						position : Vec3::new(-0.5, -0.5, -0.5),
-						color : Vec3::new(0.0, 0.0, 0.0),
-					},
-				),
-			],
-			basic_vertex_transformer,
-			basic_pixel_colorer,
-			basic_trans_env_updater,
-			basic_color_env_updater,
+				],
+				indices : vec![
+					//Front Face
+					[0, 2, 3],
+					[1, 2, 0],
+					//Back Face
+					[4, 7, 6],
+					[5, 4, 6],
+					//Top Face
+					[7, 3, 6],
+					[6, 3, 2],
+					//Bottom Face
+					[4, 5, 0],
+					[5, 1, 0],
+					//Right Face
+					[2, 1, 6],
+					[6, 1, 5],
+					//Left Face
+					[7, 0, 3],
+					[7, 4, 0],
+				],
+			},
+			BasicShader,
 			Mat4::IDENTITY,
 		)
 	}
 
-	pub fn unit_plane() -> Mesh<BasicV, BasicTE, BasicP, BasicCE> {
+	pub fn unit_plane() -> Mesh<BasicShader> {
 		Mesh::new(
-			vec![
-				//Back Left
-				Triangle::new(
+			Geometry::Indexed {
+				vertices : vec![
 					BasicV {
 						position : Vec3::new(-0.5, 0.0, 0.5),
 						color : Vec3::new(0.0, 0.0, 1.0),
@@ -262,32 +328,165 @@ impl<V, TE, P, CE> Mesh<V, TE, P, CE> {
 						position : Vec3::new(-0.5, 0.0, -0.5),
 						color : Vec3::new(0.0, 0.0, 0.0),
 					},
-				),
-				//Front Right
-				Triangle::new(
-					BasicV {
-						position : Vec3::new(0.5, 0.0, 0.5),
-						color : Vec3::new(1.0, 0.0, 1.0),
-					},
 					BasicV {
 						position : Vec3::new(0.5, 0.0, -0.5),
 						color : Vec3::new(1.0, 0.0, 0.0),
 					},
-					BasicV {
-						position : Vec3::new(-0.5, 0.0, -0.5),
-						color : Vec3::new(0.0, 0.0, 0.0),
-					},
-				),
-			],
-			basic_vertex_transformer,
-			basic_pixel_colorer,
-			basic_trans_env_updater,
-			basic_color_env_updater,
+				],
+				indices : vec![
+					//Back Left
+					[0, 1, 2],
+					//Front Right
+					[1, 3, 2],
+				],
+			},
+			BasicShader,
+			Mat4::IDENTITY,
+		)
+	}
+}
+
+//Corners of each face of a unit cube centered at the origin seen from the outside, bottom left
+//first going counter clockwise
+const CUBE_FACES : [[Vec3; 4]; 6] = [
+	//Front Face
+	[
+		Vec3::new(-0.5, -0.5, -0.5),
+		Vec3::new(0.5, -0.5, -0.5),
+		Vec3::new(0.5, 0.5, -0.5),
+		Vec3::new(-0.5, 0.5, -0.5),
+	],
+	//Back Face
+	[
+		Vec3::new(0.5, -0.5, 0.5),
+		Vec3::new(-0.5, -0.5, 0.5),
+		Vec3::new(-0.5, 0.5, 0.5),
+		Vec3::new(0.5, 0.5, 0.5),
+	],
+	//Top Face
+	[
+		Vec3::new(-0.5, 0.5, -0.5),
+		Vec3::new(0.5, 0.5, -0.5),
+		Vec3::new(0.5, 0.5, 0.5),
+		Vec3::new(-0.5, 0.5, 0.5),
+	],
+	//Bottom Face
+	[
+		Vec3::new(-0.5, -0.5, 0.5),
+		Vec3::new(0.5, -0.5, 0.5),
+		Vec3::new(0.5, -0.5, -0.5),
+		Vec3::new(-0.5, -0.5, -0.5),
+	],
+	//Right Face
+	[
+		Vec3::new(0.5, -0.5, -0.5),
+		Vec3::new(0.5, -0.5, 0.5),
+		Vec3::new(0.5, 0.5, 0.5),
+		Vec3::new(0.5, 0.5, -0.5),
+	],
+	//Left Face
+	[
+		Vec3::new(-0.5, -0.5, 0.5),
+		Vec3::new(-0.5, -0.5, -0.5),
+		Vec3::new(-0.5, 0.5, -0.5),
+		Vec3::new(-0.5, 0.5, 0.5),
+	],
+];
+
+impl Mesh<TexturedShader> {
+	//A unit cube centered at the origin with the whole demo texture on each face, upright when
+	//looking at the face from outside
+	pub fn textured_cube() -> Mesh<TexturedShader> {
+		let corner_tex_coords : [Vec2; 4] = [
+			Vec2::new(0.0, 0.0),
+			Vec2::new(1.0, 0.0),
+			Vec2::new(1.0, 1.0),
+			Vec2::new(0.0, 1.0),
+		];
+
+		let vertices : Vec<TexturedV> = CUBE_FACES
+			.iter()
+			.flat_map(|f : &[Vec3; 4]| -> [TexturedV; 4] {
+				[0, 1, 2, 3].map(|i : usize| -> TexturedV {
+					TexturedV {
+						position : f[i],
+						tex_coord : corner_tex_coords[i],
+					}
+				})
+			})
+			.collect();
+
+		Mesh::new(
+			Geometry::Indexed {
+				vertices,
+				indices : cube_face_indices(),
+			},
+			TexturedShader {
+				texture : demo_texture(),
+			},
 			Mat4::IDENTITY,
 		)
 	}
 }
 
+impl Mesh<LitShader> {
+	//A unit cube centered at the origin lit by the renderer's lights, each face has its own
+	//vertices so its normals point straight out of it
+	pub fn lit_unit_cube() -> Mesh<LitShader> {
+		let vertices : Vec<LitV> = CUBE_FACES
+			.iter()
+			.flat_map(|f : &[Vec3; 4]| -> [LitV; 4] {
+				//Opposite corners meet in the middle of the face
+				let normal : Vec3 = (f[0] + f[2]).normalize();
+
+				f.map(|position : Vec3| -> LitV {
+					LitV {
+						position,
+						normal,
+						color : Vec3::new(0.9, 0.45, 0.2),
+					}
+				})
+			})
+			.collect();
+
+		Mesh::new(
+			Geometry::Indexed {
+				vertices,
+				indices : cube_face_indices(),
+			},
+			LitShader::default(),
+			Mat4::IDENTITY,
+		)
+	}
+}
+
+//Two triangles for each of the cube's faces, with each face's 4 vertices one after the other in
+//the same order as CUBE_FACES
+fn cube_face_indices() -> Vec<[u32; 3]> {
+	(0..CUBE_FACES.len() as u32)
+		.flat_map(|f : u32| -> [[u32; 3]; 2] {
+			[[f * 4, f * 4 + 1, f * 4 + 2], [f * 4, f * 4 + 2, f * 4 + 3]]
+		})
+		.collect()
+}
+
+//How a mesh's vertex data is laid out
+#[derive(Clone, Debug)]
+pub enum Geometry<V> {
+	//Every triangle has its own copy of its vertices
+	Triangles(Vec<Triangle<V>>),
+	//Triangles are made of indices into a shared vertex list. Each vertex only goes through the
+	//vertex transformer once per draw no matter how many triangles use it
+	Indexed {
+		vertices : Vec<V>,
+		indices : Vec<[u32; 3]>,
+	},
+	//Separate line segments, for things like debug axes, bounding boxes and trajectories
+	Lines(Vec<Line<V>>),
+	//Separate points, for things like point clouds
+	Points(Vec<Point<V>>),
+}
+
 //The generic "V" here is the vertex data, in the simplest case,
 //this will just be a position Vec3
 #[derive(Debug, Clone, Copy)]
@@ -303,9 +502,27 @@ impl<V> Triangle<V> {
 	}
 }
 
+//A line segment between two vertices, goes through the same shader as triangles do
+#[derive(Debug, Clone, Copy)]
+pub struct Line<V>(pub [V; 2]);
+
+impl<V> Line<V> {
+	pub fn new(
+		v1 : V,
+		v2 : V,
+	) -> Line<V> {
+		Line([v1, v2])
+	}
+}
+
+//A single vertex drawn as a square of pixels
+#[derive(Debug, Clone, Copy)]
+pub struct Point<V>(pub V);
+
 // Creates an equilateral triangle
 // centered on the origin with side
 // lengths of 1. Vertices are red blue and green
+#[allow(clippy::excessive_precision)]
 impl Default for Triangle<BasicV> {
 	fn default() -> Triangle<BasicV> {
 		Triangle::<BasicV>::new(
@@ -325,24 +542,10 @@ impl Default for Triangle<BasicV> {
 	}
 }
 
-//Vertex Transform and Pixel Colorer Function Types
-pub type VertexTransformer<V, TE, P, CE> =
-	fn(&V, &TE, &Renderer<V, TE, P, CE>) -> VertTransOut<P>;
-
-pub type PixelColorer<V, TE, P, CE> =
-	fn(&P, &CE, &Renderer<V, TE, P, CE>) -> Pixel;
-
-//Vertex Transer and Pixel Colorer enviorment construction types
-pub type VertexEnvUpdater<V, TE, P, CE> =
-	fn(&Mesh<V, TE, P, CE>, &Renderer<V, TE, P, CE>) -> TE;
-
-pub type ColorEnvUpdater<V, TE, P, CE> =
-	fn(&Mesh<V, TE, P, CE>, &Renderer<V, TE, P, CE>) -> CE;
-
-//The output of the vertex transformer. The generic
-//type "P" is interpolated and passed to the pixel
-//coloring function
-#[derive(Debug)]
+//The output of Shader::transform_vertex. The generic
+//type "P" is interpolated and passed to
+//Shader::color_pixel
+#[derive(Debug, Clone, Copy)]
 pub struct VertTransOut<P> {
 	//This position field is the vertex's position in normalized device coordinates.
 	//PLEASE NOTE!!! THIS VALUE SHOULD **NOT** BE DIVIDED BY W AFTER BEING MULTIPLIED
@@ -350,96 +553,99 @@ pub struct VertTransOut<P> {
 	//USE IT, IT DIVIDES XYZ BY W!!
 	pub pos : Vec4,
 	//Data to be interpolated and passed to the coloring function
-	pub colorer_in : P,
+	pub varying : P,
 }
 
-//A basic basic of the types put into Mesh's generics
-#[derive(Clone, Copy, Debug)]
-pub struct BasicV {
-	pub position : Vec3,
-	pub color : Vec3,
+//How much the interpolated varying changes from the pixel being colored to the one right of
+//it (ddx) and the one below it (ddy), like dFdx and dFdy in shading languages. Mostly there for
+//picking mip levels with Texture::sample_grad. Shaders only get them if they ask for them with
+//Shader::USES_DERIVATIVES
+#[derive(Debug, Clone, Copy)]
+pub struct Derivatives<P> {
+	pub ddx : P,
+	pub ddy : P,
 }
 
-#[derive(Clone, Copy, Debug)]
-pub struct BasicTE {
-	pub p_mat : Mat4,
-	pub cm_mat : Mat4,
-}
+#[cfg(test)]
+mod tests {
+	use super::{CompareFunc, StencilOp, StencilState};
 
-//TODO: Look into derive ops crate
-#[derive(Clone, Copy, Debug)]
-pub struct BasicP {
-	pub color : Vec3,
-}
+	#[test]
+	fn compare_funcs() {
+		//Whether each one passes for a new value that's less than, equal to and greater than the
+		//existing 1
+		let expected : [(CompareFunc, [bool; 3]); 8] = [
+			(CompareFunc::Never, [false, false, false]),
+			(CompareFunc::Less, [true, false, false]),
+			(CompareFunc::LessEqual, [true, true, false]),
+			(CompareFunc::Greater, [false, false, true]),
+			(CompareFunc::GreaterEqual, [false, true, true]),
+			(CompareFunc::Equal, [false, true, false]),
+			(CompareFunc::NotEqual, [true, false, true]),
+			(CompareFunc::Always, [true, true, true]),
+		];
 
-impl Mul<f32> for BasicP {
-	type Output = BasicP;
-
-	fn mul(
-		self: BasicP,
-		rhs : f32,
-	) -> Self::Output {
-		BasicP {
-			color : self.color * rhs,
+		for (compare, passes) in expected {
+			assert_eq!(
+				[0, 1, 2].map(|new : u8| -> bool { compare.passes(new, 1) }),
+				passes,
+				"{:?}",
+				compare
+			);
+			assert_eq!(
+				[0.5, 1.0, 1.5].map(|new : f32| -> bool { compare.passes(new, 1.0) }),
+				passes,
+				"{:?}",
+				compare
+			);
 		}
 	}
-}
 
-impl Add for BasicP {
-	type Output = BasicP;
+	#[test]
+	fn stencil_ops() {
+		let state : StencilState = StencilState {
+			reference : 7,
+			..StencilState::default()
+		};
+
+		//What each one does to 0, 5 and 255
+		let expected : [(StencilOp, [u8; 3]); 8] = [
+			(StencilOp::Keep, [0, 5, 255]),
+			(StencilOp::Zero, [0, 0, 0]),
+			(StencilOp::Replace, [7, 7, 7]),
+			(StencilOp::IncrementClamp, [1, 6, 255]),
+			(StencilOp::DecrementClamp, [0, 4, 254]),
+			(StencilOp::IncrementWrap, [1, 6, 0]),
+			(StencilOp::DecrementWrap, [255, 4, 254]),
+			(StencilOp::Invert, [255, 250, 0]),
+		];
 
-	fn add(
-		self: BasicP,
-		rhs : BasicP,
-	) -> Self::Output {
-		BasicP {
-			color : self.color + rhs.color,
+		for (op, after) in expected {
+			assert_eq!(
+				[0, 5, 255].map(|stencil : u8| -> u8 { state.apply(op, stencil) }),
+				after,
+				"{:?}",
+				op
+			);
 		}
 	}
-}
 
-#[derive(Clone, Copy)]
-pub struct BasicCE {}
-
-pub fn basic_vertex_transformer(
-	vert_data : &BasicV,
-	vert_env : &BasicTE,
-	_rend : &Renderer<BasicV, BasicTE, BasicP, BasicCE>,
-) -> VertTransOut<BasicP> {
-	let mut pos : Vec4 =
-		vert_env.p_mat * vert_env.cm_mat * Vec4::from((vert_data.position, 1_f32));
-	//pos.z = pos.z.max(0.01_f32);
-
-	VertTransOut {
-		pos,
-		colorer_in : BasicP {
-			color : vert_data.color,
-		},
-	}
-}
+	//Bits outside the write mask are left alone, and the test only looks at the read mask's
+	#[test]
+	fn stencil_masks() {
+		let state : StencilState = StencilState {
+			compare : CompareFunc::Equal,
+			reference : 0b1010_0101,
+			read_mask : 0b0000_1111,
+			write_mask : 0b0000_1111,
+			..StencilState::default()
+		};
 
-pub fn basic_pixel_colorer(
-	color_data : &BasicP,
-	_color_env : &BasicCE,
-	_rend : &Renderer<BasicV, BasicTE, BasicP, BasicCE>,
-) -> Pixel {
-	Vec4::from((color_data.color, 1.0))
-}
+		assert_eq!(state.apply(StencilOp::Replace, 0b1111_0000), 0b1111_0101);
+		assert_eq!(state.apply(StencilOp::Invert, 0b1111_0000), 0b1111_1111);
+		assert_eq!(state.apply(StencilOp::IncrementWrap, 0b0000_1111), 0b0000_0000);
 
-pub fn basic_trans_env_updater(
-	m : &Mesh<BasicV, BasicTE, BasicP, BasicCE>,
-	r : &Renderer<BasicV, BasicTE, BasicP, BasicCE>,
-) -> BasicTE {
-	BasicTE {
-		//Kinda sucks that proj * camera has to be computed once per mesh now :/
-		p_mat : r.camera.proj_mat,
-		cm_mat : r.camera.camera_mat * m.model_mat,
+		assert!(state.test(0b0101_0101));
+		assert!(!state.test(0b1010_0100));
 	}
 }
-
-pub fn basic_color_env_updater(
-	_m : &Mesh<BasicV, BasicTE, BasicP, BasicCE>,
-	_r : &Renderer<BasicV, BasicTE, BasicP, BasicCE>,
-) -> BasicCE {
-	BasicCE {}
-}
@@ -0,0 +1,540 @@
+// Loader for Wavefront OBJ models and
+// the MTL material libraries they pull
+// in.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use glam::{Mat4, Vec2, Vec3};
+
+use crate::mesh::{Geometry, Mesh, Triangle};
+use crate::shader::{BasicShader, BasicV};
+
+//Vertex data that comes out of an OBJ file. Faces that don't give normals get their flat face
+//normal, and faces that don't give texture coordinates get zeroes
+#[derive(Clone, Copy, Debug)]
+pub struct ObjV {
+	pub position : Vec3,
+	pub normal : Vec3,
+	pub tex_coord : Vec2,
+}
+
+//The subset of MTL that's useful to a rasterizer
+#[derive(Clone, Debug)]
+pub struct ObjMaterial {
+	pub name : String,
+	//Ka
+	pub ambient : Vec3,
+	//Kd
+	pub diffuse : Vec3,
+	//Ks
+	pub specular : Vec3,
+	//Ns
+	pub shininess : f32,
+	//d, or 1 - Tr
+	pub opacity : f32,
+	//map_Kd, relative paths are resolved against the MTL file's directory
+	pub diffuse_map : Option<PathBuf>,
+}
+
+impl ObjMaterial {
+	fn new(name : String) -> ObjMaterial {
+		ObjMaterial {
+			name,
+			ambient : Vec3::ZERO,
+			diffuse : Vec3::ONE,
+			specular : Vec3::ZERO,
+			shininess : 0_f32,
+			opacity : 1_f32,
+			diffuse_map : None,
+		}
+	}
+}
+
+//A run of faces that share a group name and material. Switching either one starts a new group
+#[derive(Clone, Debug)]
+pub struct ObjGroup {
+	pub name : String,
+	//Index into ObjModel::materials
+	pub material : Option<usize>,
+	pub tris : Vec<Triangle<ObjV>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ObjModel {
+	pub groups : Vec<ObjGroup>,
+	pub materials : Vec<ObjMaterial>,
+}
+
+impl ObjModel {
+	//Reads an OBJ file along with any material libraries it references
+	pub fn load(path : &Path) -> Result<ObjModel, String> {
+		let source : String = read_to_string(path)?;
+
+		ObjModel::parse(
+			&source,
+			&file_name(path),
+			path.parent().unwrap_or(Path::new(".")),
+		)
+	}
+
+	//name is only used for error messages, mtllib paths are resolved against mtl_dir
+	pub fn parse(
+		source : &str,
+		name : &str,
+		mtl_dir : &Path,
+	) -> Result<ObjModel, String> {
+		let mut model : ObjModel = ObjModel::default();
+
+		let mut positions : Vec<Vec3> = Vec::new();
+		let mut normals : Vec<Vec3> = Vec::new();
+		let mut tex_coords : Vec<Vec2> = Vec::new();
+
+		let mut group_name : String = String::from("default");
+		let mut material : Option<usize> = None;
+
+		for (line_idx, line) in source.lines().enumerate() {
+			let err = |msg : String| -> String {
+				format!("{}:{}: {}", name, line_idx + 1, msg)
+			};
+
+			let mut words : std::str::SplitWhitespace =
+				strip_comment(line).split_whitespace();
+
+			let Some(keyword) = words.next() else {
+				continue;
+			};
+
+			let args : Vec<&str> = words.collect();
+
+			match keyword {
+				"v" => positions.push(parse_vec3(&args).map_err(err)?),
+				"vn" => normals.push(parse_vec3(&args).map_err(err)?),
+				"vt" => {
+					//The optional third (w) coordinate is for 3d textures, which aren't a thing here
+					let floats : Vec<f32> = parse_floats(&args, 1, 3).map_err(err)?;
+
+					tex_coords.push(Vec2::new(
+						floats[0],
+						floats.get(1).copied().unwrap_or(0_f32),
+					));
+				},
+				"f" => {
+					if args.len() < 3 {
+						return Err(err(format!(
+							"face needs at least 3 vertices, found {}",
+							args.len()
+						)));
+					}
+
+					let corners : Vec<FaceCorner> = args
+						.iter()
+						.map(|a : &&str| -> Result<FaceCorner, String> {
+							FaceCorner::parse(
+								a,
+								positions.len(),
+								tex_coords.len(),
+								normals.len(),
+							)
+						})
+						.collect::<Result<Vec<FaceCorner>, String>>()
+						.map_err(err)?;
+
+					let group : &mut ObjGroup =
+						model.current_group(&group_name, material);
+
+					//Polygons are fanned out from their first corner, which is only correct for
+					//convex ones, but that's all exporters produce in practice
+					for i in 1..corners.len() - 1 {
+						group.tris.push(make_tri(
+							[&corners[0], &corners[i], &corners[i + 1]],
+							&positions,
+							&tex_coords,
+							&normals,
+						));
+					}
+				},
+				"g" | "o" => {
+					group_name = if args.is_empty() {
+						String::from("default")
+					} else {
+						args.join(" ")
+					};
+				},
+				"usemtl" => {
+					let mtl_name : String = args.join(" ");
+
+					material = Some(
+						model
+							.materials
+							.iter()
+							.position(|m : &ObjMaterial| -> bool { m.name == mtl_name })
+							.ok_or_else(|| -> String {
+								err(format!("material {} was never defined", mtl_name))
+							})?,
+					);
+				},
+				"mtllib" => {
+					if args.is_empty() {
+						return Err(err(String::from("mtllib needs a file name")));
+					}
+
+					for lib in args {
+						let lib_path : PathBuf = mtl_dir.join(lib);
+
+						let lib_source : String =
+							read_to_string(&lib_path).map_err(err)?;
+
+						model.materials.extend(parse_mtl(
+							&lib_source,
+							&file_name(&lib_path),
+							lib_path.parent().unwrap_or(Path::new(".")),
+						)?);
+					}
+				},
+				//Smoothing groups, lines, points, curves and friends
+				_ => {},
+			}
+		}
+
+		//Groups that were named but never got any faces are just noise
+		model
+			.groups
+			.retain(|g : &ObjGroup| -> bool { !g.tris.is_empty() });
+
+		Ok(model)
+	}
+
+	//Every triangle in the model, regardless of group
+	pub fn tris(self: &ObjModel) -> Vec<Triangle<ObjV>> {
+		self.groups
+			.iter()
+			.flat_map(|g : &ObjGroup| -> std::slice::Iter<Triangle<ObjV>> {
+				g.tris.iter()
+			})
+			.copied()
+			.collect()
+	}
+
+	//For quickly looking at a model. Faces get their material's diffuse color, or their normal
+	//if they don't have a material. Corners that end up with identical vertices are merged so
+	//they're only transformed once
+	pub fn to_basic_mesh(self: &ObjModel) -> Mesh<BasicShader> {
+		let mut vertices : Vec<BasicV> = Vec::new();
+		let mut indices : Vec<[u32; 3]> = Vec::new();
+		let mut seen : HashMap<[u32; 6], u32> = HashMap::new();
+
+		self.groups.iter().for_each(|g : &ObjGroup| -> () {
+			let diffuse : Option<Vec3> =
+				g.material.map(|m : usize| -> Vec3 { self.materials[m].diffuse });
+
+			g.tris.iter().for_each(|t : &Triangle<ObjV>| -> () {
+				indices.push(t.0.map(|v : ObjV| -> u32 {
+					let basic : BasicV = BasicV {
+						position : v.position,
+						color : diffuse.unwrap_or(v.normal * 0.5 + 0.5),
+					};
+
+					let key : [u32; 6] = [
+						basic.position.x.to_bits(),
+						basic.position.y.to_bits(),
+						basic.position.z.to_bits(),
+						basic.color.x.to_bits(),
+						basic.color.y.to_bits(),
+						basic.color.z.to_bits(),
+					];
+
+					*seen.entry(key).or_insert_with(|| -> u32 {
+						vertices.push(basic);
+						vertices.len() as u32 - 1
+					})
+				}));
+			});
+		});
+
+		Mesh::new(
+			Geometry::Indexed {
+				vertices,
+				indices,
+			},
+			BasicShader,
+			Mat4::IDENTITY,
+		)
+	}
+
+	fn current_group(
+		self: &mut ObjModel,
+		name : &str,
+		material : Option<usize>,
+	) -> &mut ObjGroup {
+		let matches : bool = self.groups.last().is_some_and(|g : &ObjGroup| -> bool {
+			g.name == name && g.material == material
+		});
+
+		if !matches {
+			self.groups.push(ObjGroup {
+				name : String::from(name),
+				material,
+				tris : Vec::new(),
+			});
+		}
+
+		self.groups.last_mut().expect("A group was just pushed")
+	}
+}
+
+//Parses an MTL material library, name is only used for error messages and map paths are
+//resolved against map_dir
+pub fn parse_mtl(
+	source : &str,
+	name : &str,
+	map_dir : &Path,
+) -> Result<Vec<ObjMaterial>, String> {
+	let mut materials : Vec<ObjMaterial> = Vec::new();
+
+	for (line_idx, line) in source.lines().enumerate() {
+		let err =
+			|msg : String| -> String { format!("{}:{}: {}", name, line_idx + 1, msg) };
+
+		let mut words : std::str::SplitWhitespace =
+			strip_comment(line).split_whitespace();
+
+		let Some(keyword) = words.next() else {
+			continue;
+		};
+
+		let args : Vec<&str> = words.collect();
+
+		if keyword == "newmtl" {
+			if args.is_empty() {
+				return Err(err(String::from("newmtl needs a name")));
+			}
+
+			materials.push(ObjMaterial::new(args.join(" ")));
+
+			continue;
+		}
+
+		let Some(material) = materials.last_mut() else {
+			return Err(err(format!("{} comes before any newmtl", keyword)));
+		};
+
+		match keyword {
+			"Ka" => material.ambient = parse_vec3(&args).map_err(err)?,
+			"Kd" => material.diffuse = parse_vec3(&args).map_err(err)?,
+			"Ks" => material.specular = parse_vec3(&args).map_err(err)?,
+			"Ns" => material.shininess = parse_floats(&args, 1, 1).map_err(err)?[0],
+			"d" => material.opacity = parse_floats(&args, 1, 1).map_err(err)?[0],
+			"Tr" => material.opacity = 1_f32 - parse_floats(&args, 1, 1).map_err(err)?[0],
+			//Map options like -blendu come before the file name, which always comes last
+			"map_Kd" => {
+				material.diffuse_map =
+					Some(map_dir.join(args.last().ok_or_else(|| -> String {
+						err(String::from("map_Kd needs a file name"))
+					})?));
+			},
+			_ => {},
+		}
+	}
+
+	Ok(materials)
+}
+
+//One corner of a face, made from the 1 based (or negative, relative to the end) indices in
+//"v/vt/vn", with the texture and normal indices being optional. Stored as 0 based indices
+struct FaceCorner {
+	position : usize,
+	tex_coord : Option<usize>,
+	normal : Option<usize>,
+}
+
+impl FaceCorner {
+	fn parse(
+		s : &str,
+		position_count : usize,
+		tex_coord_count : usize,
+		normal_count : usize,
+	) -> Result<FaceCorner, String> {
+		let mut parts : std::str::Split<char> = s.split('/');
+
+		let position : usize =
+			resolve_index(parts.next().unwrap_or(""), position_count, "vertex")?
+				.ok_or_else(|| -> String {
+					format!("face corner {} has no vertex index", s)
+				})?;
+
+		let tex_coord : Option<usize> = resolve_index(
+			parts.next().unwrap_or(""),
+			tex_coord_count,
+			"texture coordinate",
+		)?;
+
+		let normal : Option<usize> =
+			resolve_index(parts.next().unwrap_or(""), normal_count, "normal")?;
+
+		if parts.next().is_some() {
+			return Err(format!("face corner {} has too many indices", s));
+		}
+
+		Ok(FaceCorner {
+			position,
+			tex_coord,
+			normal,
+		})
+	}
+}
+
+fn resolve_index(
+	s : &str,
+	count : usize,
+	what : &str,
+) -> Result<Option<usize>, String> {
+	if s.is_empty() {
+		return Ok(None);
+	}
+
+	let idx : i64 = s.parse().map_err(|_ : std::num::ParseIntError| -> String {
+		format!("{} index {} is not an integer", what, s)
+	})?;
+
+	let resolved : i64 = if idx < 0 { count as i64 + idx } else { idx - 1 };
+
+	if idx == 0 || resolved < 0 || resolved >= count as i64 {
+		return Err(format!(
+			"{} index {} is out of range, there are {} so far",
+			what, s, count
+		));
+	}
+
+	Ok(Some(resolved as usize))
+}
+
+fn make_tri(
+	corners : [&FaceCorner; 3],
+	positions : &[Vec3],
+	tex_coords : &[Vec2],
+	normals : &[Vec3],
+) -> Triangle<ObjV> {
+	let [a, b, c] : [Vec3; 3] =
+		corners.map(|fc : &FaceCorner| -> Vec3 { positions[fc.position] });
+
+	let face_normal : Vec3 = (b - a).cross(c - a).normalize_or_zero();
+
+	Triangle(corners.map(|fc : &FaceCorner| -> ObjV {
+		ObjV {
+			position : positions[fc.position],
+			normal : fc
+				.normal
+				.map_or(face_normal, |n : usize| -> Vec3 { normals[n] }),
+			tex_coord : fc
+				.tex_coord
+				.map_or(Vec2::ZERO, |t : usize| -> Vec2 { tex_coords[t] }),
+		}
+	}))
+}
+
+fn strip_comment(line : &str) -> &str {
+	line.split('#').next().unwrap_or("")
+}
+
+fn parse_floats(
+	args : &[&str],
+	min : usize,
+	max : usize,
+) -> Result<Vec<f32>, String> {
+	if args.len() < min || args.len() > max {
+		return Err(if min == max {
+			format!("expected {} numbers, found {}", min, args.len())
+		} else {
+			format!("expected {} to {} numbers, found {}", min, max, args.len())
+		});
+	}
+
+	args.iter()
+		.map(|a : &&str| -> Result<f32, String> {
+			a.parse()
+				.map_err(|_ : std::num::ParseFloatError| -> String {
+					format!("{} is not a number", a)
+				})
+		})
+		.collect()
+}
+
+//Some exporters tack a w or vertex colors onto positions, those are ignored
+fn parse_vec3(args : &[&str]) -> Result<Vec3, String> {
+	let floats : Vec<f32> = parse_floats(args, 3, 7)?;
+
+	Ok(Vec3::new(floats[0], floats[1], floats[2]))
+}
+
+fn read_to_string(path : &Path) -> Result<String, String> {
+	std::fs::read_to_string(path).map_err(|e : std::io::Error| -> String {
+		format!("Could not read {}: {}", path.display(), e)
+	})
+}
+
+fn file_name(path : &Path) -> String {
+	path.file_name().map_or_else(
+		|| -> String { path.display().to_string() },
+		|n : &std::ffi::OsStr| -> String { n.to_string_lossy().into_owned() },
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+
+	use super::ObjModel;
+
+	fn parse_err(source : &str) -> String {
+		ObjModel::parse(source, "test.obj", Path::new("missing_dir"))
+			.expect_err("The model should fail to parse")
+	}
+
+	#[test]
+	fn face_index_out_of_range() {
+		let verts : &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+
+		assert_eq!(
+			parse_err(&format!("{}f 1 2 4\n", verts)),
+			"test.obj:4: vertex index 4 is out of range, there are 3 so far"
+		);
+		assert_eq!(
+			parse_err(&format!("{}\nf 0 1 2\n", verts)),
+			"test.obj:5: vertex index 0 is out of range, there are 3 so far"
+		);
+		assert_eq!(
+			parse_err(&format!("{}f 1 2 -4\n", verts)),
+			"test.obj:4: vertex index -4 is out of range, there are 3 so far"
+		);
+	}
+
+	#[test]
+	fn malformed_float() {
+		assert_eq!(
+			parse_err("# A comment\nv 0 1.5.0 0\n"),
+			"test.obj:2: 1.5.0 is not a number"
+		);
+	}
+
+	#[test]
+	fn unknown_usemtl() {
+		assert_eq!(
+			parse_err("v 0 0 0\nusemtl shiny\n"),
+			"test.obj:2: material shiny was never defined"
+		);
+	}
+
+	#[test]
+	fn missing_mtllib() {
+		assert_eq!(parse_err("mtllib\n"), "test.obj:1: mtllib needs a file name");
+
+		let missing_file : String = parse_err("\nmtllib nowhere.mtl\n");
+
+		assert!(
+			missing_file.starts_with("test.obj:2: Could not read missing_dir/nowhere.mtl: "),
+			"{}",
+			missing_file
+		);
+	}
+}
@@ -0,0 +1,319 @@
+// Ready made post processing passes for
+// the usual full screen effects.
+
+use std::sync::Arc;
+
+use glam::{Vec2, Vec3, Vec4};
+use image::RgbImage;
+
+use super::{luma, PostProcessPass};
+use crate::pixel::Pixel;
+use crate::render_target::Frame;
+
+//Applies f to every pixel along with its position on screen, from 0 to 1 left to right and top to
+//bottom
+fn map_pixels(
+	frame : &Frame,
+	f : impl Fn(Pixel, Vec2) -> Pixel,
+) -> Vec<Pixel> {
+	let size : Vec2 = Vec2::new(frame.width as f32, frame.height as f32);
+
+	frame
+		.color
+		.iter()
+		.enumerate()
+		.map(|(i, p) : (usize, &Pixel)| -> Pixel {
+			let pos : Vec2 = Vec2::new(
+				(i as u32 % frame.width) as f32 + 0.5,
+				(i as u32 / frame.width) as f32 + 0.5,
+			);
+
+			f(*p, pos / size)
+		})
+		.collect()
+}
+
+//Raises colors to 1 / gamma, 2.2 turns linear colors into roughly what monitors expect
+pub fn gamma(gamma : f32) -> PostProcessPass {
+	Box::new(move |frame : &Frame| -> Vec<Pixel> {
+		map_pixels(frame, |p : Pixel, _pos : Vec2| -> Pixel {
+			p.truncate().max(Vec3::ZERO).powf(1_f32 / gamma).extend(p.w)
+		})
+	})
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMapOperator {
+	//c / (1 + c), gentle and never quite reaches white
+	Reinhard,
+	//Krzysztof Narkowicz's fit of the ACES filmic curve, punchier with a proper shoulder
+	Aces,
+}
+
+//Squashes colors brighter than 1 back into range after scaling them by exposure
+pub fn tone_map(
+	operator : ToneMapOperator,
+	exposure : f32,
+) -> PostProcessPass {
+	Box::new(move |frame : &Frame| -> Vec<Pixel> {
+		map_pixels(frame, |p : Pixel, _pos : Vec2| -> Pixel {
+			let c : Vec3 = p.truncate().max(Vec3::ZERO) * exposure;
+
+			let mapped : Vec3 = match operator {
+				ToneMapOperator::Reinhard => c / (Vec3::ONE + c),
+				ToneMapOperator::Aces => (c * (2.51 * c + 0.03)
+					/ (c * (2.43 * c + 0.59) + 0.14))
+					.clamp(Vec3::ZERO, Vec3::ONE),
+			};
+
+			mapped.extend(p.w)
+		})
+	})
+}
+
+//Darkens the corners. Nothing changes inside of radius (as a fraction of the distance from the
+//center to a corner), it then fades down to 1 - strength at the corners
+pub fn vignette(
+	strength : f32,
+	radius : f32,
+) -> PostProcessPass {
+	Box::new(move |frame : &Frame| -> Vec<Pixel> {
+		map_pixels(frame, |p : Pixel, pos : Vec2| -> Pixel {
+			let dist : f32 = (pos - 0.5).length() / Vec2::splat(0.5).length();
+
+			let t : f32 =
+				((dist - radius) / (1_f32 - radius).max(f32::EPSILON)).clamp(0.0, 1.0);
+			let falloff : f32 = t * t * (3_f32 - 2_f32 * t);
+
+			(p.truncate() * (1_f32 - strength * falloff)).extend(p.w)
+		})
+	})
+}
+
+//A 3D lookup table from colors to graded colors, size entries along each side
+#[derive(Debug, Clone)]
+pub struct Lut {
+	size : usize,
+	//Red changes fastest, then green, then blue
+	entries : Vec<Vec3>,
+}
+
+impl Lut {
+	pub fn from_fn(
+		size : usize,
+		f : impl Fn(Vec3) -> Vec3,
+	) -> Lut {
+		let size : usize = size.max(2);
+		let max : f32 = (size - 1) as f32;
+
+		Lut {
+			size,
+			entries : (0..size * size * size)
+				.map(|i : usize| -> Vec3 {
+					f(Vec3::new(
+						(i % size) as f32 / max,
+						(i / size % size) as f32 / max,
+						(i / (size * size)) as f32 / max,
+					))
+				})
+				.collect(),
+		}
+	}
+
+	//Leaves every color as it is
+	pub fn identity(size : usize) -> Lut {
+		Lut::from_fn(size, |c : Vec3| -> Vec3 { c })
+	}
+
+	//Reads the usual strip layout, size tiles of size by size pixels side by side where each
+	//tile is one blue value, red goes across the tile and green goes down it
+	pub fn from_image(image : &RgbImage) -> Result<Lut, String> {
+		let size : u32 = image.height();
+
+		if size < 2 || image.width() != size * size {
+			return Err(format!(
+				"A LUT strip has to be size * size by size pixels, got {}x{}",
+				image.width(),
+				image.height()
+			));
+		}
+
+		let entries : Vec<Vec3> = (0..size * size * size)
+			.map(|i : u32| -> Vec3 {
+				let (r, g, b) : (u32, u32, u32) =
+					(i % size, i / size % size, i / (size * size));
+
+				let p : &image::Rgb<u8> = image.get_pixel(b * size + r, g);
+
+				Vec3::new(p.0[0] as f32, p.0[1] as f32, p.0[2] as f32) / 255_f32
+			})
+			.collect();
+
+		Ok(Lut {
+			size : size as usize,
+			entries,
+		})
+	}
+
+	//Trilinearly interpolated lookup
+	pub fn sample(
+		self: &Lut,
+		c : Vec3,
+	) -> Vec3 {
+		let max : f32 = (self.size - 1) as f32;
+		let pos : Vec3 = c.clamp(Vec3::ZERO, Vec3::ONE) * max;
+		let base : Vec3 = pos.floor().min(Vec3::splat(max - 1_f32));
+		let t : Vec3 = pos - base;
+
+		let entry = |r : usize, g : usize, b : usize| -> Vec3 {
+			self.entries[(b * self.size + g) * self.size + r]
+		};
+
+		let (r, g, b) : (usize, usize, usize) =
+			(base.x as usize, base.y as usize, base.z as usize);
+
+		let lerp_r = |g : usize, b : usize| -> Vec3 {
+			entry(r, g, b).lerp(entry(r + 1, g, b), t.x)
+		};
+		let lerp_g = |b : usize| -> Vec3 { lerp_r(g, b).lerp(lerp_r(g + 1, b), t.y) };
+
+		lerp_g(b).lerp(lerp_g(b + 1), t.z)
+	}
+}
+
+//Color grading, every color is looked up in the LUT
+pub fn color_grade(lut : Arc<Lut>) -> PostProcessPass {
+	Box::new(move |frame : &Frame| -> Vec<Pixel> {
+		map_pixels(frame, |p : Pixel, _pos : Vec2| -> Pixel {
+			lut.sample(p.truncate()).extend(p.w)
+		})
+	})
+}
+
+//Convolves the frame with weights along one axis, clamping at the edges
+fn convolve_1d(
+	frame : &Frame,
+	color : &[Pixel],
+	weights : &[f32],
+	horizontal : bool,
+) -> Vec<Pixel> {
+	let (width, height) : (i32, i32) = (frame.width as i32, frame.height as i32);
+	let radius : i32 = (weights.len() / 2) as i32;
+
+	(0..width * height)
+		.map(|i : i32| -> Pixel {
+			let (x, y) : (i32, i32) = (i % width, i / width);
+
+			weights
+				.iter()
+				.enumerate()
+				.map(|(k, w) : (usize, &f32)| -> Vec4 {
+					let o : i32 = k as i32 - radius;
+
+					let (sx, sy) : (i32, i32) = if horizontal {
+						((x + o).clamp(0, width - 1), y)
+					} else {
+						(x, (y + o).clamp(0, height - 1))
+					};
+
+					color[(sy * width + sx) as usize] * *w
+				})
+				.sum()
+		})
+		.collect()
+}
+
+//Gaussian blur reaching radius pixels out, done as two 1D passes
+pub fn blur(radius : u32) -> PostProcessPass {
+	let sigma : f32 = (radius as f32 / 2_f32).max(0.5);
+
+	let weights : Vec<f32> = (-(radius as i32)..=radius as i32)
+		.map(|o : i32| -> f32 { (-(o * o) as f32 / (2_f32 * sigma * sigma)).exp() })
+		.collect();
+
+	let total : f32 = weights.iter().sum();
+
+	let weights : Vec<f32> = weights
+		.iter()
+		.map(|w : &f32| -> f32 { w / total })
+		.collect();
+
+	Box::new(move |frame : &Frame| -> Vec<Pixel> {
+		let horizontal : Vec<Pixel> = convolve_1d(frame, frame.color, &weights, true);
+
+		convolve_1d(frame, &horizontal, &weights, false)
+	})
+}
+
+//Unsharp masking, pushes each pixel away from the average of its 4 neighbours by amount. Only
+//brightness is sharpened so edges don't pick up colored fringes
+pub fn sharpen(amount : f32) -> PostProcessPass {
+	Box::new(move |frame : &Frame| -> Vec<Pixel> {
+		let (width, height) : (i32, i32) = (frame.width as i32, frame.height as i32);
+
+		let luma_at = |x : i32, y : i32| -> f32 {
+			luma(frame.pixel(x.clamp(0, width - 1) as u32, y.clamp(0, height - 1) as u32))
+		};
+
+		map_pixels(frame, |p : Pixel, pos : Vec2| -> Pixel {
+			let (x, y) : (i32, i32) = (
+				(pos.x * width as f32) as i32,
+				(pos.y * height as f32) as i32,
+			);
+
+			let neighbours : f32 = (luma_at(x - 1, y)
+				+ luma_at(x + 1, y)
+				+ luma_at(x, y - 1)
+				+ luma_at(x, y + 1))
+				/ 4_f32;
+
+			let detail : f32 = (luma(p) - neighbours) * amount;
+
+			(p.truncate() + Vec3::splat(detail))
+				.clamp(Vec3::ZERO, Vec3::ONE)
+				.extend(p.w)
+		})
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use glam::Vec3;
+	use image::{Rgb, RgbImage};
+
+	use super::Lut;
+
+	//Entries are hit exactly, and colors in between are blended from the entries around them
+	#[test]
+	fn lut_sample() {
+		let squared : Lut = Lut::from_fn(3, |c : Vec3| -> Vec3 { c * c });
+
+		assert_eq!(squared.sample(Vec3::new(0.0, 0.5, 1.0)), Vec3::new(0.0, 0.25, 1.0));
+		assert_eq!(squared.sample(Vec3::new(0.25, 0.75, 0.5)), Vec3::new(0.125, 0.625, 0.25));
+		//Off the ends is clamped to them
+		assert_eq!(squared.sample(Vec3::new(-1.0, 2.0, 0.5)), Vec3::new(0.0, 1.0, 0.25));
+
+		let c : Vec3 = Vec3::new(0.1, 0.6, 0.85);
+
+		assert!(Lut::identity(17).sample(c).abs_diff_eq(c, 1e-6));
+	}
+
+	//Blue picks the tile, red goes across it and green down it
+	#[test]
+	fn lut_from_image() {
+		let strip : RgbImage = RgbImage::from_fn(4, 2, |x : u32, y : u32| -> Rgb<u8> {
+			Rgb([(x % 2 * 255) as u8, (y * 255) as u8, (x / 2 * 255) as u8])
+		});
+
+		let lut : Lut = Lut::from_image(&strip).expect("The strip is the right size");
+
+		for c in [Vec3::ZERO, Vec3::ONE, Vec3::X, Vec3::Y, Vec3::Z, Vec3::new(0.25, 0.5, 0.75)] {
+			assert!(lut.sample(c).abs_diff_eq(c, 1e-6), "{} became {}", c, lut.sample(c));
+		}
+
+		assert_eq!(
+			Lut::from_image(&RgbImage::new(4, 4)).err(),
+			Some(String::from("A LUT strip has to be size * size by size pixels, got 4x4"))
+		);
+	}
+}
@@ -0,0 +1,239 @@
+// Fast approximate anti-aliasing. Finds
+// edges from differences in brightness
+// and blurs along them.
+//
+// Follows the structure of FXAA 3.11 by Timothy Lottes: each pixel works out whether it's on a
+// horizontal or vertical edge, walks along the edge both ways to find where it ends, then blends
+// with its neighbour across the edge by how close it is to an end. A second, sub-pixel term
+// smooths out features thinner than a pixel.
+
+use glam::{IVec2, Vec2};
+
+use super::{luma, sample_bilinear, PostProcessPass};
+use crate::pixel::Pixel;
+use crate::render_target::Frame;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FxaaQuality {
+	//Short edge searches and only strong edges, cheapest
+	Low,
+	Medium,
+	//Long edge searches that also pick up faint edges, blurriest
+	High,
+}
+
+struct FxaaSettings {
+	//Edges need a contrast of at least this much of the brightest neighbour...
+	edge_threshold : f32,
+	//...and at least this much overall, so dark areas aren't all treated as edges
+	edge_threshold_min : f32,
+	//How far (in pixels) each step of the search along an edge goes, the first step is taken
+	//straight away
+	search_steps : &'static [f32],
+	//How much sub-pixel aliasing is smoothed out, 0 is none
+	subpixel : f32,
+}
+
+impl FxaaQuality {
+	fn settings(self: FxaaQuality) -> FxaaSettings {
+		match self {
+			FxaaQuality::Low => FxaaSettings {
+				edge_threshold : 0.25,
+				edge_threshold_min : 0.0833,
+				search_steps : &[1.0, 1.5, 2.0, 4.0],
+				subpixel : 0.5,
+			},
+			FxaaQuality::Medium => FxaaSettings {
+				edge_threshold : 0.166,
+				edge_threshold_min : 0.0625,
+				search_steps : &[1.0, 1.0, 1.0, 1.0, 1.5, 2.0, 2.0, 4.0],
+				subpixel : 0.75,
+			},
+			FxaaQuality::High => FxaaSettings {
+				edge_threshold : 0.125,
+				edge_threshold_min : 0.0312,
+				search_steps : &[
+					1.0, 1.0, 1.0, 1.0, 1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0,
+				],
+				subpixel : 0.75,
+			},
+		}
+	}
+}
+
+//An FXAA pass for a post process chain
+pub fn fxaa(quality : FxaaQuality) -> PostProcessPass {
+	Box::new(move |frame : &Frame| -> Vec<Pixel> { apply(frame, &quality.settings()) })
+}
+
+fn apply(
+	frame : &Frame,
+	settings : &FxaaSettings,
+) -> Vec<Pixel> {
+	let (width, height) : (u32, u32) = (frame.width, frame.height);
+
+	let lumas : Vec<f32> = frame.color.iter().map(|p : &Pixel| -> f32 { luma(*p) }).collect();
+
+	(0..height as i32)
+		.flat_map(|y : i32| -> Vec<Pixel> {
+			(0..width as i32)
+				.map(|x : i32| -> Pixel { fxaa_pixel(frame, &lumas, IVec2::new(x, y), settings) })
+				.collect()
+		})
+		.collect()
+}
+
+fn fxaa_pixel(
+	frame : &Frame,
+	lumas : &[f32],
+	pixel : IVec2,
+	settings : &FxaaSettings,
+) -> Pixel {
+	let (width, height) : (u32, u32) = (frame.width, frame.height);
+
+	//Neighbouring lumas, clamped to the edges of the frame
+	let luma_at = |dx : i32, dy : i32| -> f32 {
+		let x : i32 = (pixel.x + dx).clamp(0, width as i32 - 1);
+		let y : i32 = (pixel.y + dy).clamp(0, height as i32 - 1);
+
+		lumas[(y as u32 * width + x as u32) as usize]
+	};
+
+	let center : f32 = luma_at(0, 0);
+	let up : f32 = luma_at(0, -1);
+	let down : f32 = luma_at(0, 1);
+	let left : f32 = luma_at(-1, 0);
+	let right : f32 = luma_at(1, 0);
+
+	let luma_min : f32 = center.min(up).min(down).min(left).min(right);
+	let luma_max : f32 = center.max(up).max(down).max(left).max(right);
+	let range : f32 = luma_max - luma_min;
+
+	//Not enough contrast to be an edge worth smoothing
+	if range < settings.edge_threshold_min.max(luma_max * settings.edge_threshold) {
+		return frame.pixel(pixel.x as u32, pixel.y as u32);
+	}
+
+	let up_left : f32 = luma_at(-1, -1);
+	let up_right : f32 = luma_at(1, -1);
+	let down_left : f32 = luma_at(-1, 1);
+	let down_right : f32 = luma_at(1, 1);
+
+	let up_down : f32 = up + down;
+	let left_right : f32 = left + right;
+
+	let left_corners : f32 = up_left + down_left;
+	let right_corners : f32 = up_right + down_right;
+	let up_corners : f32 = up_left + up_right;
+	let down_corners : f32 = down_left + down_right;
+
+	//Which way the brightness changes the most, a horizontal edge has its big change going up
+	//or down
+	let edge_horizontal : f32 = (-2_f32 * left + left_corners).abs()
+		+ (-2_f32 * center + up_down).abs() * 2_f32
+		+ (-2_f32 * right + right_corners).abs();
+	let edge_vertical : f32 = (-2_f32 * up + up_corners).abs()
+		+ (-2_f32 * center + left_right).abs() * 2_f32
+		+ (-2_f32 * down + down_corners).abs();
+
+	let is_horizontal : bool = edge_horizontal >= edge_vertical;
+
+	//The neighbours on either side of the edge, the "negative" one is up or left
+	let (luma_neg, luma_pos) : (f32, f32) = if is_horizontal {
+		(up, down)
+	} else {
+		(left, right)
+	};
+
+	let gradient_neg : f32 = luma_neg - center;
+	let gradient_pos : f32 = luma_pos - center;
+
+	//The edge is between this pixel and whichever side changes the most
+	let neg_steepest : bool = gradient_neg.abs() >= gradient_pos.abs();
+
+	let gradient_scaled : f32 = 0.25 * gradient_neg.abs().max(gradient_pos.abs());
+
+	let (step_length, luma_local_average) : (f32, f32) = if neg_steepest {
+		(-1_f32, 0.5 * (luma_neg + center))
+	} else {
+		(1_f32, 0.5 * (luma_pos + center))
+	};
+
+	//Directions across and along the edge
+	let (across, along) : (Vec2, Vec2) = if is_horizontal {
+		(Vec2::Y, Vec2::X)
+	} else {
+		(Vec2::X, Vec2::Y)
+	};
+
+	let center_pos : Vec2 = pixel.as_vec2() + 0.5;
+
+	//Halfway between this pixel and the one across the edge, right on the edge
+	let edge_pos : Vec2 = center_pos + across * step_length * 0.5;
+
+	let edge_luma = |pos : Vec2| -> f32 {
+		sample_bilinear(lumas, width, height, pos) - luma_local_average
+	};
+
+	//Walk along the edge both ways until the brightness along it changes enough that the edge
+	//must have ended
+	let mut pos_neg : Vec2 = edge_pos - along * settings.search_steps[0];
+	let mut pos_pos : Vec2 = edge_pos + along * settings.search_steps[0];
+
+	let mut luma_end_neg : f32 = edge_luma(pos_neg);
+	let mut luma_end_pos : f32 = edge_luma(pos_pos);
+
+	for step in &settings.search_steps[1..] {
+		let reached_neg : bool = luma_end_neg.abs() >= gradient_scaled;
+		let reached_pos : bool = luma_end_pos.abs() >= gradient_scaled;
+
+		if reached_neg && reached_pos {
+			break;
+		}
+
+		if !reached_neg {
+			pos_neg -= along * *step;
+			luma_end_neg = edge_luma(pos_neg);
+		}
+
+		if !reached_pos {
+			pos_pos += along * *step;
+			luma_end_pos = edge_luma(pos_pos);
+		}
+	}
+
+	let distance_neg : f32 = (center_pos - pos_neg).dot(along);
+	let distance_pos : f32 = (pos_pos - center_pos).dot(along);
+
+	let (distance, luma_end) : (f32, f32) = if distance_neg < distance_pos {
+		(distance_neg, luma_end_neg)
+	} else {
+		(distance_pos, luma_end_pos)
+	};
+
+	let edge_length : f32 = distance_neg + distance_pos;
+
+	//Pixels close to the end of an edge get blended the most. If the brightness at the closer end
+	//goes the same way as this pixel's, this pixel is on the wrong side of it to need anything
+	let edge_offset : f32 = if (luma_end < 0_f32) != (center < luma_local_average) {
+		0.5 - distance / edge_length
+	} else {
+		0_f32
+	};
+
+	//How different this pixel is from the average around it, relative to the contrast
+	let luma_average : f32 =
+		(2_f32 * (up_down + left_right) + left_corners + right_corners) / 12_f32;
+	let subpixel_blend : f32 = ((luma_average - center).abs() / range).clamp(0_f32, 1_f32);
+	let subpixel_blend : f32 = (-2_f32 * subpixel_blend + 3_f32) * subpixel_blend * subpixel_blend;
+	let subpixel_offset : f32 = subpixel_blend * subpixel_blend * settings.subpixel;
+
+	let offset : f32 = edge_offset.max(subpixel_offset);
+
+	sample_bilinear(
+		frame.color,
+		width,
+		height,
+		center_pos + across * step_length * offset,
+	)
+}
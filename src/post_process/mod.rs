@@ -0,0 +1,50 @@
+// Passes that run over the finished frame
+// buffer after everything has been drawn,
+// one after the other.
+
+pub mod effects;
+pub mod fxaa;
+
+use glam::{Vec2, Vec3};
+
+use crate::pixel::Pixel;
+use crate::render_target::Frame;
+
+//Takes the frame as the previous pass (or draw) left it and gives back the new colors, one per
+//pixel in the same order
+pub type PostProcessPass = Box<dyn FnMut(&Frame) -> Vec<Pixel>>;
+
+//How bright a pixel looks, ignoring alpha
+pub fn luma(p : Pixel) -> f32 {
+	p.truncate().dot(Vec3::new(0.299, 0.587, 0.114))
+}
+
+//Bilinear lookup in a width by height buffer, with pos in pixels where pixel centers are at +0.5.
+//Anything off the edge is clamped to it
+pub fn sample_bilinear<T>(
+	buffer : &[T],
+	width : u32,
+	height : u32,
+	pos : Vec2,
+) -> T
+where
+	T : Copy + std::ops::Mul<f32, Output = T> + std::ops::Add<Output = T>,
+{
+	let p : Vec2 = pos - 0.5;
+	let base : Vec2 = p.floor();
+	let t : Vec2 = p - base;
+
+	let texel = |x : i32, y : i32| -> T {
+		let x : u32 = x.clamp(0, width as i32 - 1) as u32;
+		let y : u32 = y.clamp(0, height as i32 - 1) as u32;
+
+		buffer[(y * width + x) as usize]
+	};
+
+	let (x, y) : (i32, i32) = (base.x as i32, base.y as i32);
+
+	let top : T = texel(x, y) * (1_f32 - t.x) + texel(x + 1, y) * t.x;
+	let bottom : T = texel(x, y + 1) * (1_f32 - t.x) + texel(x + 1, y + 1) * t.x;
+
+	top * (1_f32 - t.y) + bottom * t.y
+}
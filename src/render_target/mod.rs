@@ -0,0 +1,68 @@
+// Things that finished frames can be
+// presented to, be it a window, files on
+// disk or plain memory.
+
+use crate::pixel::Pixel;
+use crate::renderer::Renderer;
+
+//A finished frame, borrowed from whatever drew it
+pub struct Frame<'a> {
+	pub width : u32,
+	pub height : u32,
+	//Row major, top left pixel first
+	pub color : &'a [Pixel],
+	//Not every source has depth to give, so targets that want it have to handle it missing
+	pub depth : Option<&'a [f32]>,
+}
+
+impl<'a> Frame<'a> {
+	pub fn pixel(
+		self: &Frame<'a>,
+		x : u32,
+		y : u32,
+	) -> Pixel {
+		self.color[(y * self.width + x) as usize]
+	}
+}
+
+pub trait RenderTarget {
+	//Called once for every frame the renderer finishes
+	fn present(
+		&mut self,
+		frame : &Frame,
+	) -> Result<(), String>;
+}
+
+//Steps the renderer and presents the result to the target, frame_count times over. Targets that
+//drive their own loop (like the window, which waits on its event loop) don't go through this
+pub fn run_frames(
+	source : &mut Renderer,
+	target : &mut dyn RenderTarget,
+	frame_count : u32,
+) -> Result<(), String> {
+	for _ in 0..frame_count {
+		source.frame_step();
+
+		target.present(&source.frame())?;
+	}
+
+	Ok(())
+}
+
+//Pixel components are meant to stay in 0.0..=1.0, but nothing stops a shader from going past
+//that so they get clamped on the way out
+pub fn channel_to_u8(c : f32) -> u8 {
+	(c.clamp(0_f32, 1_f32) * u8::MAX as f32).round() as u8
+}
+
+//Alpha is dropped
+pub fn pixel_to_rgb8(p : &Pixel) -> [u8; 3] {
+	[channel_to_u8(p.x), channel_to_u8(p.y), channel_to_u8(p.z)]
+}
+
+//0RGB packing, which is what softbuffer wants
+pub fn pixel_to_u32(p : &Pixel) -> u32 {
+	let [r, g, b] : [u8; 3] = pixel_to_rgb8(p);
+
+	(r as u32) << 16 | (g as u32) << 8 | b as u32
+}
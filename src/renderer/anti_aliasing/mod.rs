@@ -0,0 +1,75 @@
+// Smoothing out the jagged edges of
+// triangles by looking at more than one
+// spot inside of each pixel.
+
+use glam::I64Vec2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AntiAliasing {
+	//Each pixel is only sampled at its center
+	None,
+	//Coverage, depth and stencil are worked out per sample, but the shader only runs once
+	//per pixel and its color is shared by every sample the triangle covers
+	Msaa(SampleCount),
+	//Everything including the shader runs per sample. Looks the best, especially for
+	//textures, but costs as much as rendering at that many times the resolution
+	Ssaa(SampleCount),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleCount {
+	X2,
+	X4,
+	X8,
+}
+
+//The standard sample patterns GPUs use, in 1/16ths of a pixel from its center with y going down
+const PATTERN_1 : [(i64, i64); 1] = [(0, 0)];
+const PATTERN_2 : [(i64, i64); 2] = [(4, 4), (-4, -4)];
+const PATTERN_4 : [(i64, i64); 4] = [(-2, -6), (6, -2), (-6, 2), (2, 6)];
+const PATTERN_8 : [(i64, i64); 8] = [
+	(1, -3),
+	(-1, 3),
+	(5, 1),
+	(-3, -5),
+	(-5, 5),
+	(-7, -1),
+	(3, 7),
+	(7, -7),
+];
+
+impl AntiAliasing {
+	pub fn sample_count(self: AntiAliasing) -> usize {
+		self.sample_pattern().len()
+	}
+
+	//Whether the shader runs for every sample instead of once per pixel
+	pub fn per_sample_shading(self: AntiAliasing) -> bool {
+		matches!(self, AntiAliasing::Ssaa(_))
+	}
+
+	//Where each sample is relative to the pixel center, in fixed point with subpixel_bits of
+	//precision
+	pub fn sample_offsets(
+		self: AntiAliasing,
+		subpixel_bits : u32,
+	) -> Vec<I64Vec2> {
+		self.sample_pattern()
+			.iter()
+			.map(|(x, y) : &(i64, i64)| -> I64Vec2 {
+				I64Vec2::new(*x, *y) * (1 << subpixel_bits) / 16
+			})
+			.collect()
+	}
+
+	fn sample_pattern(self: AntiAliasing) -> &'static [(i64, i64)] {
+		match self {
+			AntiAliasing::None => &PATTERN_1,
+			AntiAliasing::Msaa(count) | AntiAliasing::Ssaa(count) => match count {
+				SampleCount::X2 => &PATTERN_2,
+				SampleCount::X4 => &PATTERN_4,
+				SampleCount::X8 => &PATTERN_8,
+			},
+		}
+	}
+}
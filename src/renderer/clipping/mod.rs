@@ -0,0 +1,180 @@
+// Clips triangles against the view
+// frustum in homogeneous clip space,
+// before anything gets divided by w.
+
+use glam::{Vec3, Vec4};
+
+use crate::interpolate::{Interpolate, Weights};
+use crate::mesh::VertTransOut;
+
+//Signed distances from a clip space point to each of the planes bounding the view volume,
+//points with a non negative distance are on the visible side. glam's left handed projection
+//matrices put the visible depth range at 0..=w
+const CLIP_PLANES : [fn(&Vec4) -> f32; 6] = [
+	//Left
+	|p : &Vec4| -> f32 { p.w + p.x },
+	//Right
+	|p : &Vec4| -> f32 { p.w - p.x },
+	//Bottom
+	|p : &Vec4| -> f32 { p.w + p.y },
+	//Top
+	|p : &Vec4| -> f32 { p.w - p.y },
+	//Near
+	|p : &Vec4| -> f32 { p.z },
+	//Far
+	|p : &Vec4| -> f32 { p.w - p.z },
+];
+
+//Sutherland-Hodgman clipping of a single triangle. What comes out is the convex polygon (in the
+//same winding order as the input) that is left of the triangle inside the view volume, which is
+//empty if none of it is visible. Triangles that are fully inside come back untouched, otherwise
+//every vertex gets its #[flat] fields from verts[provoking]
+pub fn clip_tri<P : Interpolate>(
+	verts : [VertTransOut<P>; 3],
+	provoking : usize,
+) -> Vec<VertTransOut<P>> {
+	let mut polygon : Vec<VertTransOut<P>> = Vec::from(verts);
+	let mut clipped : bool = false;
+
+	for plane in CLIP_PLANES {
+		if polygon
+			.iter()
+			.all(|v : &VertTransOut<P>| -> bool { plane(&v.pos) >= 0_f32 })
+		{
+			continue;
+		}
+
+		clipped = true;
+
+		let input : Vec<VertTransOut<P>> = std::mem::take(&mut polygon);
+
+		for i in 0..input.len() {
+			let cur : &VertTransOut<P> = &input[i];
+			let next : &VertTransOut<P> = &input[(i + 1) % input.len()];
+
+			let cur_dist : f32 = plane(&cur.pos);
+			let next_dist : f32 = plane(&next.pos);
+
+			if cur_dist >= 0_f32 {
+				polygon.push(*cur);
+			}
+
+			//Edge crosses the plane, keep the point where it does
+			if (cur_dist >= 0_f32) != (next_dist >= 0_f32) {
+				polygon.push(lerp_vert(cur, next, cur_dist / (cur_dist - next_dist)));
+			}
+		}
+
+		if polygon.len() < 3 {
+			return Vec::new();
+		}
+	}
+
+	if clipped {
+		polygon = polygon
+			.iter()
+			.map(|v : &VertTransOut<P>| -> VertTransOut<P> {
+				with_flat_of(v, &verts[provoking].varying)
+			})
+			.collect();
+	}
+
+	polygon
+}
+
+//Cuts a line down to the part of it inside the view volume, None if none of it is. The ends stay
+//in the same order. New ends get their #[flat] fields from the start, so both ends should
+//already have the same ones
+pub fn clip_line<P : Interpolate>(verts : [VertTransOut<P>; 2]) -> Option<[VertTransOut<P>; 2]> {
+	//How far along the line the visible part starts and ends
+	let (mut t_start, mut t_end) : (f32, f32) = (0_f32, 1_f32);
+
+	for plane in CLIP_PLANES {
+		let start_dist : f32 = plane(&verts[0].pos);
+		let end_dist : f32 = plane(&verts[1].pos);
+
+		if start_dist < 0_f32 && end_dist < 0_f32 {
+			return None;
+		}
+
+		if start_dist < 0_f32 {
+			t_start = t_start.max(start_dist / (start_dist - end_dist));
+		} else if end_dist < 0_f32 {
+			t_end = t_end.min(start_dist / (start_dist - end_dist));
+		}
+	}
+
+	if t_start > t_end {
+		return None;
+	}
+
+	//Ends that are already inside are left untouched, same as with triangles
+	Some([
+		if t_start > 0_f32 {
+			lerp_vert(&verts[0], &verts[1], t_start)
+		} else {
+			verts[0]
+		},
+		if t_end < 1_f32 {
+			lerp_vert(&verts[0], &verts[1], t_end)
+		} else {
+			verts[1]
+		},
+	])
+}
+
+//Whether a point is inside the view volume, points are either drawn whole or not at all
+pub fn point_visible<P>(vert : &VertTransOut<P>) -> bool {
+	CLIP_PLANES
+		.iter()
+		.all(|plane : &fn(&Vec4) -> f32| -> bool { plane(&vert.pos) >= 0_f32 })
+}
+
+//Clip space is still linear with respect to the original triangle, so both the position and
+//the varying can be interpolated directly without any perspective correction. Screen linear
+//varyings need to know how far along the new vertex is once it's been divided by w though
+fn lerp_vert<P : Interpolate>(
+	a : &VertTransOut<P>,
+	b : &VertTransOut<P>,
+	t : f32,
+) -> VertTransOut<P> {
+	let pos : Vec4 = a.pos.lerp(b.pos, t);
+
+	//Only vertices in front of the camera have a screen position, anything else gets cut off
+	//by the near plane before it could be seen
+	let screen_t : f32 = if a.pos.w > 0_f32 && b.pos.w > 0_f32 {
+		t * b.pos.w / pos.w
+	} else {
+		t
+	};
+
+	VertTransOut {
+		pos,
+		varying : P::interpolate(
+			[&a.varying, &b.varying, &b.varying],
+			&Weights {
+				perspective : Vec3::new(1_f32 - t, t, 0_f32),
+				linear : Vec3::new(1_f32 - screen_t, screen_t, 0_f32),
+				flat : Vec3::X,
+			},
+		),
+	}
+}
+
+//The vertex with its #[flat] fields swapped for the provoking vertex's
+pub fn with_flat_of<P : Interpolate>(
+	vert : &VertTransOut<P>,
+	provoking : &P,
+) -> VertTransOut<P> {
+	VertTransOut {
+		pos : vert.pos,
+		varying : P::interpolate(
+			[&vert.varying, provoking, provoking],
+			&Weights {
+				perspective : Vec3::X,
+				linear : Vec3::X,
+				flat : Vec3::Y,
+			},
+		),
+	}
+}
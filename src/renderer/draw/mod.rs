@@ -0,0 +1,99 @@
+// Meshes with their shader types hidden,
+// so meshes with different shaders can
+// be drawn into the same frame.
+
+use std::any::Any;
+
+use glam::IVec2;
+
+use super::primitive::{SetupLine, SetupPoint};
+use super::tile::Tile;
+use super::{MeshBatch, Primitive, Renderer, RendererSettings, SetupTri};
+use crate::mesh::Mesh;
+use crate::shader::Shader;
+
+//Something the renderer can draw, which is any Mesh whatever its shader is. It's Any so the
+//renderer can hand meshes back as what they really are, see Renderer::mesh
+pub trait Drawable : Any + Sync {
+	fn setup<'a>(
+		&'a self,
+		renderer : &Renderer,
+	) -> DrawCommand<'a>;
+}
+
+impl<S : Shader> Drawable for Mesh<S> {
+	fn setup<'a>(
+		self: &'a Mesh<S>,
+		renderer : &Renderer,
+	) -> DrawCommand<'a> {
+		DrawCommand {
+			batch : Box::new(renderer.setup_mesh(self)),
+		}
+	}
+}
+
+//A set up mesh ready to be rastered into tiles
+pub struct DrawCommand<'a> {
+	pub(super) batch : Box<dyn RasterBatch + 'a>,
+}
+
+//Everything drawing needs from a MeshBatch that doesn't care what its shader is
+pub(super) trait RasterBatch : Sync {
+	fn blended(&self) -> bool;
+
+	fn camera_dist(&self) -> f32;
+
+	//The screen space bounding box of every primitive, inclusive
+	fn bounds(&self) -> Vec<(IVec2, IVec2, Primitive)>;
+
+	fn raster(
+		&self,
+		settings : &RendererSettings,
+		primitive : Primitive,
+		tile : &mut Tile,
+	) -> ();
+}
+
+impl<'a, S : Shader> RasterBatch for MeshBatch<'a, S> {
+	fn blended(self: &MeshBatch<'a, S>) -> bool {
+		self.blend_state.is_some()
+	}
+
+	fn camera_dist(self: &MeshBatch<'a, S>) -> f32 {
+		self.camera_dist
+	}
+
+	fn bounds(self: &MeshBatch<'a, S>) -> Vec<(IVec2, IVec2, Primitive)> {
+		//Lines come after the triangles so wireframe overlays are drawn on top of them
+		self.tris
+			.iter()
+			.enumerate()
+			.map(|(i, t) : (usize, &SetupTri<S::Varying>)| -> (IVec2, IVec2, Primitive) {
+				(t.min, t.max, Primitive::Tri(i))
+			})
+			.chain(self.lines.iter().enumerate().map(
+				|(i, l) : (usize, &SetupLine<S::Varying>)| -> (IVec2, IVec2, Primitive) {
+					(l.min, l.max, Primitive::Line(i))
+				},
+			))
+			.chain(self.points.iter().enumerate().map(
+				|(i, p) : (usize, &SetupPoint<S::Varying>)| -> (IVec2, IVec2, Primitive) {
+					(p.min, p.max, Primitive::Point(i))
+				},
+			))
+			.collect()
+	}
+
+	fn raster(
+		self: &MeshBatch<'a, S>,
+		settings : &RendererSettings,
+		primitive : Primitive,
+		tile : &mut Tile,
+	) -> () {
+		match primitive {
+			Primitive::Tri(i) => Renderer::raster_tri(settings, &self.tris[i], self, tile),
+			Primitive::Line(i) => Renderer::raster_line(settings, &self.lines[i], self, tile),
+			Primitive::Point(i) => Renderer::raster_point(settings, &self.points[i], self, tile),
+		}
+	}
+}
@@ -1,69 +1,119 @@
 // Module that handles the main loop of
 // drawing and rendering.
 
+mod anti_aliasing;
 mod camera;
+mod clipping;
+mod draw;
+mod primitive;
+mod tile;
 
-use std::ops::{Add, Mul, Not};
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 
 use camera::Camera;
-use glam::{IVec2, Mat3, Mat4, Vec3, Vec3Swizzles, Vec4Swizzles};
+use glam::{I64Vec2, IVec2, Mat3, Vec2, Vec3, Vec4, Vec4Swizzles};
 
+use crate::blend::BlendState;
+use crate::interpolate::{Interpolate, Weights};
+use crate::lighting::Light;
 use crate::mesh::{
+	CullMode,
+	DepthState,
+	Derivatives,
+	FrontFace,
+	Geometry,
+	Line,
 	Mesh,
-	PixelColorer,
+	Point,
+	PolygonMode,
+	StencilOp,
+	StencilState,
 	Triangle,
 	VertTransOut,
-	VertexTransformer,
 };
 use crate::pixel::Pixel;
+use crate::post_process::PostProcessPass;
+use crate::render_target::Frame;
+use crate::shader::Shader;
+pub use anti_aliasing::{AntiAliasing, SampleCount};
+use draw::DrawCommand;
+pub use draw::Drawable;
+use primitive::{SetupLine, SetupPoint};
+use tile::{Fragment, Tile};
 
-//The main renderer. For information on what these type generics do, please refer to
-//./src/mesh/mod.rs
-pub struct Renderer<V, TE, P, CE> {
+//The main renderer. Meshes can each have a different shader, see ./src/shader/mod.rs
+pub struct Renderer {
 	// Main frame buffer that is written to
 	pub frame_buffer : Vec<Pixel>,
 	// Depth buffer that is used for knowing what tris are visible
 	pub depth_buffer : Vec<f32>,
+	// Stencil buffer meshes can mark and test against
+	pub stencil_buffer : Vec<u8>,
 	// Settings for how to draw things
 	pub renderer_settings : RendererSettings,
 	// Camera that holds the camera and projection matrix
 	pub camera : Camera,
-	// Triangles to be rastered
-	pub meshes : Vec<Mesh<V, TE, P, CE>>,
+	// Lights in the scene, for shaders
+	// that use them like LitShader
+	pub lights : Vec<Light>,
+	// Meshes to be rastered, in whatever
+	// shader they were made with
+	pub meshes : Vec<Box<dyn Drawable>>,
+	// Run over the frame buffer in order once everything has been drawn
+	pub post_process : Vec<PostProcessPass>,
 	// Update function to run before drawing each frame
-	update_fn : Option<UpdateFunc<V, TE, P, CE>>,
+	update_fn : Option<UpdateFunc>,
 }
 
-impl<V, TE, P, CE> Renderer<V, TE, P, CE>
-where
-	V : Clone + Copy,
-	TE : Clone,
-	P : Clone + Copy + Mul<f32, Output = P> + Add<Output = P>,
-	CE : Clone,
-{
+impl Renderer {
 	pub fn new(
 		renderer_settings : RendererSettings,
-		meshes : Vec<Mesh<V, TE, P, CE>>,
-		update_fn : Option<Box<dyn FnMut(&mut Renderer<V, TE, P, CE>) -> ()>>,
-	) -> Renderer<V, TE, P, CE> {
+		meshes : Vec<Box<dyn Drawable>>,
+		update_fn : Option<UpdateFunc>,
+	) -> Renderer {
 		let pix_area : usize =
 			(renderer_settings.width * renderer_settings.height) as usize;
 
 		Renderer {
 			frame_buffer : vec![renderer_settings.background_col; pix_area],
-			depth_buffer : vec![f32::MAX; pix_area],
+			depth_buffer : vec![renderer_settings.depth_clear; pix_area],
+			stencil_buffer : vec![renderer_settings.stencil_clear; pix_area],
 			renderer_settings,
 			camera : Camera::default(),
+			lights : Vec::new(),
 			meshes,
+			post_process : Vec::new(),
 			update_fn,
 		}
 	}
 
-	pub fn width(self: &Renderer<V, TE, P, CE>) -> u32 {
+	//One of the meshes as the mesh it really is, None if it has another shader
+	pub fn mesh<S : Shader>(
+		self: &Renderer,
+		idx : usize,
+	) -> Option<&Mesh<S>> {
+		let mesh : &dyn Any = self.meshes.get(idx)?.as_ref();
+
+		mesh.downcast_ref::<Mesh<S>>()
+	}
+
+	pub fn mesh_mut<S : Shader>(
+		self: &mut Renderer,
+		idx : usize,
+	) -> Option<&mut Mesh<S>> {
+		let mesh : &mut dyn Any = self.meshes.get_mut(idx)?.as_mut();
+
+		mesh.downcast_mut::<Mesh<S>>()
+	}
+
+	pub fn width(self: &Renderer) -> u32 {
 		self.renderer_settings.width
 	}
 
-	pub fn height(self: &Renderer<V, TE, P, CE>) -> u32 {
+	pub fn height(self: &Renderer) -> u32 {
 		self.renderer_settings.height
 	}
 
@@ -71,166 +121,395 @@ where
 	// NDC and pixel coordinates and vice
 	// versa
 	pub fn screen_x_to_ndx(
-		self: &Renderer<V, TE, P, CE>,
+		self: &Renderer,
 		x : i32,
 	) -> f32 {
-		x as f32 / self.width() as f32 * 2_f32 - 1_f32
+		self.renderer_settings.screen_x_to_ndx(x)
 	}
 
 	pub fn screen_y_to_ndy(
-		self: &Renderer<V, TE, P, CE>,
+		self: &Renderer,
 		y : i32,
 	) -> f32 {
-		(1_f32 - (y as f32 / self.height() as f32)) * 2_f32 - 1_f32
+		self.renderer_settings.screen_y_to_ndy(y)
 	}
 
 	pub fn screen_coords_to_ndc(
-		self: &Renderer<V, TE, P, CE>,
+		self: &Renderer,
 		c : IVec2,
 	) -> Vec3 {
 		Vec3::new(self.screen_x_to_ndx(c.x), self.screen_y_to_ndy(c.y), 0_f32)
 	}
 
 	pub fn ndx_to_screen_x(
-		self: &Renderer<V, TE, P, CE>,
+		self: &Renderer,
 		x : f32,
 	) -> i32 {
-		f32::round(self.width() as f32 * ((1_f32 + x) / 2_f32)) as i32
+		self.renderer_settings.ndx_to_screen_x(x)
 	}
 
 	pub fn ndy_to_screen_y(
-		self: &Renderer<V, TE, P, CE>,
+		self: &Renderer,
 		y : f32,
 	) -> i32 {
-		f32::round(self.height() as f32 * (1_f32 - ((1_f32 + y) / 2_f32))) as i32
+		self.renderer_settings.ndy_to_screen_y(y)
 	}
 
 	pub fn ndc_to_screen_coords(
-		self: &Renderer<V, TE, P, CE>,
+		self: &Renderer,
 		p : &Vec3,
 	) -> IVec2 {
 		IVec2::new(self.ndx_to_screen_x(p.x), self.ndy_to_screen_y(p.y))
 	}
 
-	//A triangle is on screen if any of its points are in NDC range and
-	fn tri_visible(
-		self: &Renderer<V, TE, P, CE>,
-		transformed_verts : &[VertTransOut<P>; 3],
-	) -> bool {
-		transformed_verts
-			.into_iter()
-			.any(|v : &VertTransOut<P>| -> bool { v.pos.x > -1_f32 })
-			&& transformed_verts
-				.into_iter()
-				.any(|v : &VertTransOut<P>| -> bool { v.pos.x < 1_f32 })
-			&& transformed_verts
-				.into_iter()
-				.any(|v : &VertTransOut<P>| -> bool { v.pos.y > -1_f32 })
-			&& transformed_verts
-				.into_iter()
-				.any(|v : &VertTransOut<P>| -> bool { v.pos.y < 1_f32 })
-			&& transformed_verts
-				.into_iter()
-				.any(|v : &VertTransOut<P>| -> bool {
-					v.pos.z > self.camera.near_plane
-				})
-	}
-
-	// Draw a single triangle to the
-	// frame_buffer
-	fn raster_tri(
-		self: &mut Renderer<V, TE, P, CE>,
-		tri : &Triangle<V>,
-		vertex_transformer : VertexTransformer<V, TE, P, CE>,
-		transformer_env : &TE,
-		pixel_colorer : PixelColorer<V, TE, P, CE>,
-		color_env : &CE,
-	) -> () {
-		let mut trans_out : [VertTransOut<P>; 3] =
-			tri.0.map(|v : V| -> VertTransOut<P> {
-				vertex_transformer(&v, transformer_env, self)
-			});
+	// Transform, clip and set up every
+	// triangle of a mesh for rasterizing
+	fn setup_mesh<'a, S : Shader>(
+		self: &Renderer,
+		m : &'a Mesh<S>,
+	) -> MeshBatch<'a, S> {
+		let uniforms : S::Uniforms = m.shader.uniforms(m, self);
 
-		//TODO: make this not clip triangles that are in view but have all 3 points out of NDC
-		if !self.tri_visible(&trans_out) {
-			//println!("TRI CULLED!!!");
-			return;
-		}
+		let polygon_mode : PolygonMode =
+			self.renderer_settings.polygon_mode.unwrap_or(m.polygon_mode);
+
+		let mut batch : MeshBatch<S> = MeshBatch {
+			tris : Vec::new(),
+			lines : Vec::new(),
+			points : Vec::new(),
+			shader : &m.shader,
+			uniforms,
+			depth_state : m.depth_state,
+			stencil_state : m.stencil_state,
+			blend_state : m.blend_state,
+			camera_dist : self
+				.camera
+				.camera_mat
+				.transform_point3(m.model_mat.w_axis.truncate())
+				.length(),
+			overlay : polygon_mode == PolygonMode::FillWireframe,
+		};
+
+		// Edges and corners shared between
+		// triangles of an indexed mesh are
+		// only drawn once. Edges remember
+		// which line they became, if any
+		let mut seen_edges : HashMap<(u32, u32), Option<usize>> = HashMap::new();
+		let mut seen_verts : HashSet<u32> = HashSet::new();
+
+		let mut add_tri = |trans_out : [VertTransOut<S::Varying>; 3], idx : Option<[u32; 3]>| -> () {
+			if is_culled(m, &trans_out) {
+				return;
+			}
+
+			let first_tri : usize = batch.tris.len();
+
+			//Edges and corners drawn for the triangle take its #[flat] fields, not their own
+			let provoking : S::Varying =
+				trans_out[self.renderer_settings.provoking_vertex.index(3)].varying;
+
+			if polygon_mode.fills() {
+				self.setup_tri(trans_out, &mut batch.tris);
+			}
+
+			//Clipping leaves the pieces of a triangle on the same plane, so any of them will do
+			let depth_bias : f32 = match batch.tris.get(first_tri) {
+				Some(tri) if batch.overlay => self.overlay_depth_bias(tri),
+				_ => 0_f32,
+			};
+
+			//Edges are clipped on their own so the edges clipping adds to triangles never show up
+			if polygon_mode.draws_edges() {
+				for (i, j) in [(0, 1), (1, 2), (2, 0)] {
+					let edge : Option<(u32, u32)> = idx.map(|idx : [u32; 3]| -> (u32, u32) {
+						(idx[i].min(idx[j]), idx[i].max(idx[j]))
+					});
+
+					//Already drawn for a neighbouring triangle, it just has to stay on top of
+					//this one as well
+					if let Some(line) = edge.and_then(|e : (u32, u32)| -> Option<&Option<usize>> {
+						seen_edges.get(&e)
+					}) {
+						if let Some(line) = line {
+							let line : &mut SetupLine<S::Varying> = &mut batch.lines[*line];
+
+							line.depth_bias = line.depth_bias.max(depth_bias);
+						}
+
+						continue;
+					}
+
+					let first_line : usize = batch.lines.len();
+
+					self.setup_line(
+						[trans_out[i], trans_out[j]],
+						&provoking,
+						m.line_width,
+						depth_bias,
+						&mut batch.lines,
+					);
+
+					if let Some(e) = edge {
+						seen_edges.insert(e, (batch.lines.len() > first_line).then_some(first_line));
+					}
+				}
+			}
 
-		let mut x_sorted : [&VertTransOut<P>; 3] = trans_out.each_ref();
+			if polygon_mode == PolygonMode::Points {
+				for i in 0..3 {
+					let first_time : bool = idx
+						.is_none_or(|idx : [u32; 3]| -> bool { seen_verts.insert(idx[i]) });
 
-		x_sorted.sort_by(
-			|a : &&VertTransOut<P>, b : &&VertTransOut<P>| -> std::cmp::Ordering {
-				a.pos.x.total_cmp(&b.pos.x)
+					if first_time {
+						self.setup_point(
+							trans_out[i],
+							&provoking,
+							m.point_size,
+							&mut batch.points,
+						);
+					}
+				}
+			}
+		};
+
+		match &m.geometry {
+			Geometry::Triangles(tris) => {
+				tris.iter().for_each(|t : &Triangle<S::Vertex>| -> () {
+					let trans_out : [VertTransOut<S::Varying>; 3] =
+						t.0.map(|v : S::Vertex| -> VertTransOut<S::Varying> {
+							m.shader.transform_vertex(&v, &batch.uniforms)
+						});
+
+					add_tri(trans_out, None);
+				});
 			},
-		);
+			Geometry::Indexed {
+				vertices,
+				indices,
+			} => {
+				// Post transform cache so shared
+				// vertices are only transformed once
+				let trans_cache : Vec<VertTransOut<S::Varying>> = vertices
+					.iter()
+					.map(|v : &S::Vertex| -> VertTransOut<S::Varying> {
+						m.shader.transform_vertex(v, &batch.uniforms)
+					})
+					.collect();
 
-		let mut y_sorted : [&VertTransOut<P>; 3] = trans_out.each_ref();
+				indices.iter().for_each(|idx : &[u32; 3]| -> () {
+					//A bad index is a bug in whatever built the mesh, release builds just leave
+					//the triangle out rather than panicking halfway through a frame
+					let in_range : bool =
+						idx.iter().all(|i : &u32| -> bool { (*i as usize) < trans_cache.len() });
+
+					debug_assert!(
+						in_range,
+						"Triangle {:?} indexes past the end of {} vertices",
+						idx,
+						trans_cache.len()
+					);
 
-		y_sorted.sort_by(
-			|a : &&VertTransOut<P>, b : &&VertTransOut<P>| -> std::cmp::Ordering {
-				b.pos.y.total_cmp(&a.pos.y)
+					if !in_range {
+						return;
+					}
+
+					let trans_out : [VertTransOut<S::Varying>; 3] =
+						idx.map(|i : u32| -> VertTransOut<S::Varying> { trans_cache[i as usize] });
+
+					add_tri(trans_out, Some(*idx));
+				});
 			},
-		);
+			//Lines and points have no faces, so culling and polygon modes don't apply to them
+			Geometry::Lines(lines) => {
+				lines.iter().for_each(|l : &Line<S::Vertex>| -> () {
+					let trans_out : [VertTransOut<S::Varying>; 2] =
+						l.0.map(|v : S::Vertex| -> VertTransOut<S::Varying> {
+							m.shader.transform_vertex(&v, &batch.uniforms)
+						});
+
+					let provoking : S::Varying =
+						trans_out[self.renderer_settings.provoking_vertex.index(2)].varying;
+
+					self.setup_line(
+						trans_out,
+						&provoking,
+						m.line_width,
+						0_f32,
+						&mut batch.lines,
+					);
+				});
+			},
+			Geometry::Points(points) => {
+				points.iter().for_each(|p : &Point<S::Vertex>| -> () {
+					let trans_out : VertTransOut<S::Varying> =
+						m.shader.transform_vertex(&p.0, &batch.uniforms);
+
+					self.setup_point(
+						trans_out,
+						&trans_out.varying,
+						m.point_size,
+						&mut batch.points,
+					);
+				});
+			},
+		}
+
+		batch
+	}
+
+	// Clip a single already transformed
+	// triangle and set up whatever is left
+	fn setup_tri<P : Interpolate>(
+		self: &Renderer,
+		trans_out : [VertTransOut<P>; 3],
+		out : &mut Vec<SetupTri<P>>,
+	) -> () {
+		// Cut the triangle down to the part that's inside the view frustum, what's left is a
+		// convex polygon that gets fanned back out into triangles
+		let provoking : usize = self.renderer_settings.provoking_vertex.index(3);
+
+		let clipped : Vec<VertTransOut<P>> = clipping::clip_tri(trans_out, provoking);
+
+		//Anything clipped gets its #[flat] fields from the provoking vertex all over, so it
+		//doesn't matter which vertex of the fan ends up provoking
+		for i in 1..clipped.len().saturating_sub(1) {
+			if let Some(tri) =
+				self.setup_clipped_tri([clipped[0], clipped[i], clipped[i + 1]], provoking)
+			{
+				out.push(tri);
+			}
+		}
+	}
 
-		// Perspective space NDC coordinates of scanline ndc y bounds
-		// We are applying perspective correction with the w divide
-		let ndc_top_y : f32 = y_sorted[0].pos.y / y_sorted[0].pos.w;
-		let ndc_mid_y : f32 = y_sorted[1].pos.y / y_sorted[1].pos.w;
-		let ndc_bot_y : f32 = y_sorted[2].pos.y / y_sorted[2].pos.w;
+	// Work out everything scanline
+	// rasterization needs for a triangle
+	// that is entirely inside the view
+	// frustum, so every w is positive
+	fn setup_clipped_tri<P : Interpolate>(
+		self: &Renderer,
+		verts : [VertTransOut<P>; 3],
+		provoking : usize,
+	) -> Option<SetupTri<P>> {
+		//Which of verts ends up where once they're sorted from top to bottom
+		let mut order : [usize; 3] = [0, 1, 2];
 
-		// Camera space Z coordinates
-		let cam_top_z : f32 = y_sorted[0].pos.z;
-		let cam_mid_z : f32 = y_sorted[1].pos.z;
-		let cam_bot_z : f32 = y_sorted[2].pos.z;
+		order.sort_by(|a : &usize, b : &usize| -> std::cmp::Ordering {
+			let (a, b) : (Vec4, Vec4) = (verts[*a].pos, verts[*b].pos);
+
+			(b.y / b.w).total_cmp(&(a.y / a.w))
+		});
+
+		let y_sorted : [VertTransOut<P>; 3] =
+			order.map(|i : usize| -> VertTransOut<P> { verts[i] });
+
+		// Perspective divided NDC coordinates of the vertices. The convention we will use is
+		// y_sorted[0] is "a", y_sorted[1] is "b" and y_sorted[2] is "c"
+		let ndc_verts : [Vec3; 3] =
+			y_sorted.map(|v : VertTransOut<P>| -> Vec3 { v.pos.xyz() / v.pos.w });
+
+		//The matrix that converts a point in NDC to its screen space barycentric coords
+		let screen_bary_inv : Mat3 = Mat3::from_cols(
+			Vec3::new(ndc_verts[0].x, ndc_verts[0].y, 1_f32),
+			Vec3::new(ndc_verts[1].x, ndc_verts[1].y, 1_f32),
+			Vec3::new(ndc_verts[2].x, ndc_verts[2].y, 1_f32),
+		);
+
+		//Triangle is seen edge on
+		if screen_bary_inv.determinant() == 0_f32 {
+			return None;
+		}
 
 		// Screen coordinates of scanline screen y bounds
-		let screen_top_y : i32 = self.ndy_to_screen_y(ndc_top_y);
-		let screen_mid_y : i32 = self.ndy_to_screen_y(ndc_mid_y);
-		let screen_bot_y : i32 = self.ndy_to_screen_y(ndc_bot_y);
-
-		// slices of bounds so the two iterations can be under one loop
-		let ndc_y_bounds : [f32; 3] = [ndc_top_y, ndc_mid_y, ndc_bot_y];
-		let cam_z_bounds : [f32; 3] = [cam_top_z, cam_mid_z, cam_bot_z];
-		let screen_y_bounds : [i32; 3] = [screen_top_y, screen_mid_y, screen_bot_y];
-
-		//The matrix that converts a point in projected space to a vector of the world space
-		//barycentric coords. The convention we will use is y_sorted[0] is "a", y_sorted[1] is "b" and y_sorted[2] is
-		//"c". Formula is from https://andrewkchan.dev/posts/perspective-interpolation.html
-		let bary_mat : Mat3 = Mat3::from_diagonal(Vec3::new(
-			1_f32 / y_sorted[0].pos.z,
-			1_f32 / y_sorted[1].pos.z,
-			1_f32 / y_sorted[2].pos.z,
-		))
-		.mul(
-			Mat3::from_cols(
-				y_sorted[0].pos.xyz() / y_sorted[0].pos.w,
-				y_sorted[1].pos.xyz() / y_sorted[1].pos.w,
-				y_sorted[2].pos.xyz() / y_sorted[2].pos.w,
+		let screen_y_bounds : [i32; 3] =
+			ndc_verts.map(|v : Vec3| -> i32 { self.ndy_to_screen_y(v.y) });
+
+		let screen_verts : [Vec2; 3] = ndc_verts.map(|v : Vec3| -> Vec2 {
+			Vec2::new(
+				self.width() as f32 * ((1_f32 + v.x) / 2_f32),
+				self.height() as f32 * (1_f32 - ((1_f32 + v.y) / 2_f32)),
 			)
-			.inverse(),
-		);
+		});
 
-		for i in 0..=1 {
-			let ndc_initial_y : f32 = ndc_y_bounds[i];
-			let ndc_final_y : f32 = ndc_y_bounds[i + 1];
+		let screen_min : Vec2 = screen_verts[0].min(screen_verts[1]).min(screen_verts[2]);
+		let screen_max : Vec2 = screen_verts[0].max(screen_verts[1]).max(screen_verts[2]);
+
+		let max_pixel : IVec2 = IVec2::new(self.width() as i32 - 1, self.height() as i32 - 1);
+
+		Some(SetupTri {
+			y_sorted,
+			provoking : order
+				.iter()
+				.position(|i : &usize| -> bool { *i == provoking })
+				.unwrap_or(0),
+			ndc_verts,
+			screen_y_bounds,
+			fixed_verts : screen_verts.map(|v : Vec2| -> I64Vec2 {
+				(v * (1 << SUBPIXEL_BITS) as f32).round().as_i64vec2()
+			}),
+			//Rounded outwards so it covers whatever either rasterizer could touch
+			min : screen_min.floor().as_ivec2().clamp(IVec2::ZERO, max_pixel),
+			max : screen_max.ceil().as_ivec2().clamp(IVec2::ZERO, max_pixel),
+			bary_mat : screen_bary_inv.inverse(),
+			//Screen space barycentrics are divided by these and renormalized to get the
+			//perspective correct ones. Formula is from
+			//https://andrewkchan.dev/posts/perspective-interpolation.html
+			inv_w : Vec3::new(
+				1_f32 / y_sorted[0].pos.w,
+				1_f32 / y_sorted[1].pos.w,
+				1_f32 / y_sorted[2].pos.w,
+			),
+			//Unlike the varying, NDC depth is linear in screen space
+			ndc_z : Vec3::new(ndc_verts[0].z, ndc_verts[1].z, ndc_verts[2].z),
+		})
+	}
 
-			let cam_initial_z : f32 = cam_z_bounds[i];
-			let cam_final_z : f32 = cam_z_bounds[i + 1];
+	// Draw the part of a set up triangle
+	// inside of a tile with whichever
+	// rasterizer is selected
+	fn raster_tri<S : Shader>(
+		settings : &RendererSettings,
+		tri : &SetupTri<S::Varying>,
+		batch : &MeshBatch<S>,
+		tile : &mut Tile,
+	) -> () {
+		//Only the edge function rasterizer can tell which samples inside of a pixel are covered
+		if settings.anti_aliasing != AntiAliasing::None {
+			return Self::raster_tri_edge_function(settings, tri, batch, tile);
+		}
 
-			let screen_initial_y : i32 = screen_y_bounds[i];
-			let screen_final_y : i32 = screen_y_bounds[i + 1];
+		match settings.rasterizer {
+			Rasterizer::Scanline => Self::raster_tri_scanline(settings, tri, batch, tile),
+			Rasterizer::EdgeFunction => Self::raster_tri_edge_function(settings, tri, batch, tile),
+		}
+	}
+
+	// Scanline rasterization of the part of
+	// a set up triangle inside of a tile
+	fn raster_tri_scanline<S : Shader>(
+		settings : &RendererSettings,
+		tri : &SetupTri<S::Varying>,
+		batch : &MeshBatch<S>,
+		tile : &mut Tile,
+	) -> () {
+		let [screen_top_y, _, screen_bot_y] : [i32; 3] = tri.screen_y_bounds;
+
+		let no_derivs : Derivatives<S::Varying> = tri.no_derivatives();
+
+		for i in 0..=1 {
+			let screen_initial_y : i32 = tri.screen_y_bounds[i];
+			let screen_final_y : i32 = tri.screen_y_bounds[i + 1];
 
 			if screen_initial_y == screen_final_y {
 				continue;
 			}
 
-			let top_edge : i32 = screen_initial_y.clamp(0, self.height() as i32 - 1);
-			let bot_edge : i32 = screen_final_y.clamp(0, self.height() as i32 - 1);
+			let top_edge : i32 = screen_initial_y.clamp(0, settings.height as i32 - 1);
+			let bot_edge : i32 = screen_final_y.clamp(0, settings.height as i32 - 1);
 
-			// Iterate over lines of triangle - clamped to height for the **PERF**
-			for y in top_edge..=bot_edge {
-				let ndc_y = self.screen_y_to_ndy(y);
+			// Iterate over lines of triangle - clamped to height for the **PERF**, then cut down to
+			// the lines inside of the tile
+			for y in top_edge.max(tile.min.y)..=bot_edge.min(tile.max.y) {
+				let ndc_y = settings.screen_y_to_ndy(y);
 
 				let t : f32 = (y - screen_initial_y) as f32
 					/ (screen_final_y - screen_initial_y) as f32;
@@ -238,118 +517,472 @@ where
 				// We can easily find the y coordinate
 				// from the side formed by 2 lines
 				let mut ndc_lef_x : f32 = <f32 as glam::FloatExt>::lerp(
-					y_sorted[i].pos.x / y_sorted[i].pos.w,
-					y_sorted[i + 1].pos.x / y_sorted[i + 1].pos.w,
+					tri.ndc_verts[i].x,
+					tri.ndc_verts[i + 1].x,
 					t,
 				);
 
 				let t : f32 =
 					(y - screen_top_y) as f32 / (screen_bot_y - screen_top_y) as f32;
 
-				let mut ndc_rig_x : f32 = <f32 as glam::FloatExt>::lerp(
-					y_sorted[0].pos.x / y_sorted[0].pos.w,
-					y_sorted[2].pos.x / y_sorted[2].pos.w,
-					t,
-				);
+				let mut ndc_rig_x : f32 =
+					<f32 as glam::FloatExt>::lerp(tri.ndc_verts[0].x, tri.ndc_verts[2].x, t);
 
 				if ndc_lef_x > ndc_rig_x {
 					std::mem::swap(&mut ndc_lef_x, &mut ndc_rig_x);
 				}
 
 				//Put bounds into screen pixel coords
-				let screen_lef_x : i32 = self.ndx_to_screen_x(ndc_lef_x);
-				let screen_rig_x : i32 = self.ndx_to_screen_x(ndc_rig_x);
+				let screen_lef_x : i32 = settings.ndx_to_screen_x(ndc_lef_x);
+				let screen_rig_x : i32 = settings.ndx_to_screen_x(ndc_rig_x);
 
 				//Iterate over each horizontal pixel - also clamped for perf and to prevent drawing
 				//in the next scan line
-				let lef_edge : i32 = screen_lef_x.clamp(0, self.width() as i32 - 1);
-				let rig_edge : i32 = screen_rig_x.clamp(0, self.width() as i32 - 1);
+				let lef_edge : i32 = screen_lef_x.clamp(0, settings.width as i32 - 1);
+				let rig_edge : i32 = screen_rig_x.clamp(0, settings.width as i32 - 1);
 
-				for x in lef_edge..=rig_edge {
+				for x in lef_edge.max(tile.min.x)..=rig_edge.min(tile.max.x) {
 					//PER PIXEL OPERATIONS HERE! :D
-					let ndc_x : f32 = self.screen_x_to_ndx(x);
+					let ndc_x : f32 = settings.screen_x_to_ndx(x);
+
+					let screen_bary : Vec3 = tri.bary_mat * Vec3::new(ndc_x, ndc_y, 1_f32);
+
+					let z : f32 = screen_bary.dot(tri.ndc_z);
+
+					let pixel_idx : usize = tile.idx(x, y);
+
+					if Self::depth_stencil_test(batch, tile, pixel_idx, z) {
+						let weights : Weights = tri.weights(screen_bary);
+						let p : S::Varying = tri.interpolate(&weights);
+
+						//The neighbouring pixels' varying is worked out the same way, even
+						//if they're outside of the triangle
+						let derivs : Derivatives<S::Varying> = if S::USES_DERIVATIVES {
+							let right : Vec3 =
+								Vec3::new(settings.screen_x_to_ndx(x + 1), ndc_y, 1_f32);
+							let below : Vec3 =
+								Vec3::new(ndc_x, settings.screen_y_to_ndy(y + 1), 1_f32);
+
+							Derivatives {
+								ddx : tri.interpolate(
+									&(tri.weights(tri.bary_mat * right) - weights),
+								),
+								ddy : tri.interpolate(
+									&(tri.weights(tri.bary_mat * below) - weights),
+								),
+							}
+						} else {
+							no_derivs
+						};
+
+						let fill : Pixel =
+							batch.shader.color_pixel(&p, &derivs, &batch.uniforms);
+
+						Self::write_pixel(settings, batch, tile, pixel_idx, fill, z, i == 1);
+					}
+				}
+			}
+		}
+	}
+
+	// Edge function rasterization of the
+	// part of a set up triangle inside of
+	// a tile
+	fn raster_tri_edge_function<S : Shader>(
+		settings : &RendererSettings,
+		tri : &SetupTri<S::Varying>,
+		batch : &MeshBatch<S>,
+		tile : &mut Tile,
+	) -> () {
+		let [a, b, c] : [I64Vec2; 3] = tri.fixed_verts;
+
+		//Twice the signed area, positive when the vertices go clockwise on screen
+		let area : i64 = edge_function(a, b, c);
+
+		//Snapping can squash thin triangles down to nothing
+		if area == 0 {
+			return;
+		}
+
+		//Edges are wound so the inside of the triangle is always on the positive side, each one
+		//is paired with the vertex opposite it so its value is that vertex's barycentric weight
+		let (verts, weights) : ([I64Vec2; 3], [usize; 3]) = if area > 0 {
+			([a, b, c], [0, 1, 2])
+		} else {
+			([a, c, b], [0, 2, 1])
+		};
+
+		let edges : [(I64Vec2, I64Vec2); 3] =
+			[(verts[1], verts[2]), (verts[2], verts[0]), (verts[0], verts[1])];
+
+		let edge_deltas : [I64Vec2; 3] =
+			edges.map(|(from, to) : (I64Vec2, I64Vec2)| -> I64Vec2 { to - from });
+
+		//Pixels exactly on an edge only belong to the triangle if it is a top or left edge, the
+		//others need to be strictly inside. Screen y goes down, so top edges are flat with the
+		//inside below them and left edges go up
+		let bias : [i64; 3] = edges.map(|(from, to) : (I64Vec2, I64Vec2)| -> i64 {
+			let d : I64Vec2 = to - from;
+
+			if (d.y == 0 && d.x > 0) || d.y < 0 {
+				0
+			} else {
+				-1
+			}
+		});
+
+		//Edge function values moved by an offset in fixed point
+		let offset_edges = |e : [i64; 3], offset : I64Vec2| -> [i64; 3] {
+			[0, 1, 2].map(|i : usize| -> i64 {
+				e[i] + edge_deltas[i].x * offset.y - edge_deltas[i].y * offset.x
+			})
+		};
+
+		//How much each edge function changes going one pixel right or down
+		let step_x : [i64; 3] = offset_edges([0; 3], I64Vec2::new(1 << SUBPIXEL_BITS, 0));
+		let step_y : [i64; 3] = offset_edges([0; 3], I64Vec2::new(0, 1 << SUBPIXEL_BITS));
+
+		let covered = |e : [i64; 3]| -> bool { (0..3).all(|i : usize| -> bool { e[i] + bias[i] >= 0 }) };
+
+		let inv_area : f32 = 1_f32 / area.abs() as f32;
+
+		//Screen space barycentrics of the y sorted vertices from edge function values
+		let screen_bary = |e : [i64; 3]| -> Vec3 {
+			let mut bary : [f32; 3] = [0_f32; 3];
+
+			(0..3).for_each(|i : usize| -> () {
+				bary[weights[i]] = e[i] as f32 * inv_area;
+			});
+
+			Vec3::from_array(bary)
+		};
+
+		let min : IVec2 = tri.min.max(tile.min);
+		let max : IVec2 = tri.max.min(tile.max);
+
+		//Edge values are stepped from pixel centers, samples are offset from there
+		let half : i64 = 1 << (SUBPIXEL_BITS - 1);
+		let first_sample : I64Vec2 = I64Vec2::new(
+			((min.x as i64) << SUBPIXEL_BITS) + half,
+			((min.y as i64) << SUBPIXEL_BITS) + half,
+		);
+
+		let mut row : [i64; 3] = edges.map(|(from, to) : (I64Vec2, I64Vec2)| -> i64 {
+			edge_function(from, to, first_sample)
+		});
+
+		let no_derivs : Derivatives<S::Varying> = tri.no_derivatives();
+
+		//Runs the shader with the varying at the spot the edge values are from
+		let shade = |e : [i64; 3]| -> Pixel {
+			let weights : Weights = tri.weights(screen_bary(e));
+			let p : S::Varying = tri.interpolate(&weights);
+
+			//How much the weights change going one pixel over
+			let step = |step : [i64; 3]| -> Weights {
+				tri.weights(screen_bary([0, 1, 2].map(|i : usize| -> i64 { e[i] + step[i] })))
+					- weights
+			};
+
+			let derivs : Derivatives<S::Varying> = if S::USES_DERIVATIVES {
+				Derivatives {
+					ddx : tri.interpolate(&step(step_x)),
+					ddy : tri.interpolate(&step(step_y)),
+				}
+			} else {
+				no_derivs
+			};
 
-					let [a, b, c] : [f32; 3] = Vec3::normalize(
-						bary_mat * Vec3::new(ndc_x, ndc_y, self.camera.near_plane),
-					)
-					.to_array();
+			batch.shader.color_pixel(&p, &derivs, &batch.uniforms)
+		};
 
-					let z : f32 = y_sorted[0].pos.z * a
-						+ y_sorted[1].pos.z * b
-						+ y_sorted[2].pos.z * c;
+		let anti_aliasing : AntiAliasing = settings.anti_aliasing;
+		let sample_offsets : Vec<I64Vec2> = anti_aliasing.sample_offsets(SUBPIXEL_BITS);
 
-					if z < self.camera.near_plane {
+		//Rows below the middle vertex are the second phase, to match the scanline rasterizer
+		let screen_mid_y : i64 = tri.fixed_verts[1].y;
+
+		for y in min.y..=max.y {
+			let mut e : [i64; 3] = row;
+
+			let second_phase : bool = ((y as i64) << SUBPIXEL_BITS) + half > screen_mid_y;
+
+			for x in min.x..=max.x {
+				//PER PIXEL OPERATIONS HERE! :D
+				let pixel_idx : usize = tile.idx(x, y);
+
+				//Without per sample shading the shader runs at the pixel center the first time
+				//a sample needs it, even if the center is outside of the triangle
+				let mut pixel_fill : Option<Pixel> = None;
+
+				for (s, offset) in sample_offsets.iter().enumerate() {
+					let sample_e : [i64; 3] = offset_edges(e, *offset);
+
+					if !covered(sample_e) {
 						continue;
 					}
 
-					let pixel_fb_idx : usize = usize::min(
-						(y * self.width() as i32 + x) as usize,
-						self.frame_buffer.len() - 1,
-					);
+					let z : f32 = screen_bary(sample_e).dot(tri.ndc_z);
 
-					let p : P = y_sorted[0].colorer_in * a
-						+ y_sorted[1].colorer_in * b
-						+ y_sorted[2].colorer_in * c;
-
-					if z < self.depth_buffer[pixel_fb_idx] {
-						let fill : Pixel = pixel_colorer(&p, &color_env, self);
-
-						self.frame_buffer[pixel_fb_idx] =
-							if !self.renderer_settings.show_tri_div {
-								fill
-							} else {
-								if i == 0 {
-									fill
-								} else {
-									Pixel::ONE - fill
-								}
-							};
-
-						self.depth_buffer[pixel_fb_idx] = z;
+					let sample_idx : usize = pixel_idx * sample_offsets.len() + s;
+
+					if !Self::depth_stencil_test(batch, tile, sample_idx, z) {
+						continue;
 					}
+
+					let fill : Pixel = if anti_aliasing.per_sample_shading() {
+						shade(sample_e)
+					} else {
+						*pixel_fill.get_or_insert_with(|| -> Pixel { shade(e) })
+					};
+
+					Self::write_pixel(settings, batch, tile, sample_idx, fill, z, second_phase);
 				}
+
+				(0..3).for_each(|i : usize| -> () { e[i] += step_x[i] });
 			}
+
+			(0..3).for_each(|i : usize| -> () { row[i] += step_y[i] });
 		}
 	}
 
-	pub fn draw(self: &mut Renderer<V, TE, P, CE>) -> () {
-		// Raster all triangles
-		self
-			.frame_buffer
-			.fill(self.renderer_settings.background_col);
+	// Run the stencil and depth tests for
+	// a pixel, updating the stencil buffer
+	// with whatever the outcome says to.
+	// True if the pixel should be drawn
+	fn depth_stencil_test<S : Shader>(
+		batch : &MeshBatch<S>,
+		tile : &mut Tile,
+		pixel_idx : usize,
+		z : f32,
+	) -> bool {
+		let depth_passed : bool = batch.depth_state.compare.passes(z, tile.depth[pixel_idx]);
 
-		self.depth_buffer.fill(f32::MAX);
+		let Some(stencil_state) = &batch.stencil_state else {
+			return depth_passed;
+		};
 
-		let _proj_cam_mat : Mat4 = self.camera.proj_mat * self.camera.camera_mat;
+		let stencil : &mut u8 = &mut tile.stencil[pixel_idx];
 
-		self
-			.meshes
-			.clone()
-			.into_iter()
-			.for_each(|m : Mesh<V, TE, P, CE>| -> () {
-				let trans_env : TE = (m.trans_env_updater)(&m, self);
-
-				let color_env : CE = (m.color_env_updater)(&m, self);
-
-				m.tris.iter().for_each(|t : &Triangle<V>| -> () {
-					self.raster_tri(
-						&t,
-						m.vertex_transformer,
-						&trans_env,
-						m.pixel_colorer,
-						&color_env,
-					);
+		//The depth test only counts if the stencil test passed first
+		let (op, passed) : (StencilOp, bool) = if !stencil_state.test(*stencil) {
+			(stencil_state.fail, false)
+		} else if !depth_passed {
+			(stencil_state.depth_fail, false)
+		} else {
+			(stencil_state.pass, true)
+		};
+
+		*stencil = stencil_state.apply(op, *stencil);
+
+		passed
+	}
+
+	// Put a colored pixel that passed the
+	// depth test into a tile
+	fn write_pixel<S : Shader>(
+		settings : &RendererSettings,
+		batch : &MeshBatch<S>,
+		tile : &mut Tile,
+		pixel_idx : usize,
+		fill : Pixel,
+		z : f32,
+		second_phase : bool,
+	) -> () {
+		let fill : Pixel = if settings.show_tri_div && second_phase {
+			Pixel::ONE - fill
+		} else {
+			fill
+		};
+
+		match (&batch.blend_state, settings.transparency) {
+			(None, _) => tile.color[pixel_idx] = fill,
+			(Some(blend_state), Transparency::SortedMeshes) => {
+				tile.color[pixel_idx] = blend_state.blend(fill, tile.color[pixel_idx]);
+			},
+			//Blending waits until everything has been drawn, so these never write depth
+			(Some(blend_state), Transparency::FragmentLists) => {
+				tile.fragments[pixel_idx].push(Fragment {
+					color : fill,
+					depth : z,
+					blend_state : *blend_state,
 				});
-			});
+
+				return;
+			},
+		}
+
+		if batch.depth_state.write {
+			tile.depth[pixel_idx] = z;
+		}
+	}
+
+	// Raster everything binned to a tile,
+	// in the order it was submitted
+	fn raster_tile(
+		settings : &RendererSettings,
+		commands : &[DrawCommand],
+		bin : &[(usize, Primitive)],
+		tile : &mut Tile,
+	) -> () {
+		bin.iter().for_each(|(command_idx, primitive) : &(usize, Primitive)| -> () {
+			commands[*command_idx].batch.raster(settings, *primitive, tile);
+		});
+
+		if settings.transparency == Transparency::FragmentLists {
+			tile.resolve_fragments();
+		}
+	}
+
+	pub fn draw(self: &mut Renderer) -> () {
+		// Everything that has to happen
+		// once per triangle happens up front
+		let mut commands : Vec<DrawCommand> = self
+			.meshes
+			.iter()
+			.map(Box::as_ref)
+			.map(|m : &dyn Drawable| -> DrawCommand { m.setup(self) })
+			.collect();
+
+		// Opaque meshes go first in the order
+		// they were given, then blended ones
+		// from furthest to closest so they
+		// cover what's behind them
+		commands.sort_by(
+			|a : &DrawCommand, b : &DrawCommand| -> std::cmp::Ordering {
+				match (a.batch.blended(), b.batch.blended()) {
+					(false, false) => std::cmp::Ordering::Equal,
+					(false, true) => std::cmp::Ordering::Less,
+					(true, false) => std::cmp::Ordering::Greater,
+					(true, true) => b.batch.camera_dist().total_cmp(&a.batch.camera_dist()),
+				}
+			},
+		);
+
+		let tile_rects : Vec<(IVec2, IVec2)> = tile::tile_rects(
+			self.width(),
+			self.height(),
+			self.renderer_settings.tile_size,
+		);
+
+		// Each tile gets the list of the
+		// primitives touching it, in order
+		let mut bins : Vec<Vec<(usize, Primitive)>> = vec![Vec::new(); tile_rects.len()];
+
+		let tiles_x : usize = self.width().div_ceil(self.renderer_settings.tile_size.max(1)) as usize;
+		let tile_size : i32 = self.renderer_settings.tile_size.max(1) as i32;
+
+		commands.iter().enumerate().for_each(
+			|(command_idx, command) : (usize, &DrawCommand)| -> () {
+				command.batch.bounds().into_iter().for_each(
+					|(min, max, primitive) : (IVec2, IVec2, Primitive)| -> () {
+						for ty in min.y / tile_size..=max.y / tile_size {
+							for tx in min.x / tile_size..=max.x / tile_size {
+								bins[ty as usize * tiles_x + tx as usize]
+									.push((command_idx, primitive));
+							}
+						}
+					},
+				);
+			},
+		);
+
+		let next_tile : AtomicUsize = AtomicUsize::new(0);
+
+		//The raster threads only get to see the settings, commands and bins
+		let settings : &RendererSettings = &self.renderer_settings;
+
+		// Tiles are handed out to whichever
+		// thread asks for work next
+		let raster_tiles = || -> Vec<Tile> {
+			let mut done : Vec<Tile> = Vec::new();
+
+			loop {
+				let tile_idx : usize = next_tile.fetch_add(1, Ordering::Relaxed);
+
+				let Some((min, max)) = tile_rects.get(tile_idx) else {
+					return done;
+				};
+
+				let mut tile : Tile = Tile::new(
+					*min,
+					*max,
+					settings.anti_aliasing.sample_count(),
+					settings.background_col,
+					settings.depth_clear,
+					settings.stencil_clear,
+					settings.transparency == Transparency::FragmentLists,
+				);
+
+				Self::raster_tile(settings, &commands, &bins[tile_idx], &mut tile);
+
+				done.push(tile);
+			}
+		};
+
+		let tiles : Vec<Tile> = if settings.threads <= 1 {
+			raster_tiles()
+		} else {
+			thread::scope(|s : &thread::Scope| -> Vec<Tile> {
+				let workers : Vec<thread::ScopedJoinHandle<Vec<Tile>>> = (0..settings.threads)
+					.map(|_ : usize| -> thread::ScopedJoinHandle<Vec<Tile>> { s.spawn(raster_tiles) })
+					.collect();
+
+				workers
+					.into_iter()
+					.flat_map(|w : thread::ScopedJoinHandle<Vec<Tile>>| -> Vec<Tile> {
+						w.join().expect("Raster threads should not panic")
+					})
+					.collect()
+			})
+		};
+
+		let width : i32 = self.width() as i32;
+
+		tiles.iter().for_each(|t : &Tile| -> () {
+			t.write_back(
+				width,
+				&mut self.frame_buffer,
+				&mut self.depth_buffer,
+				&mut self.stencil_buffer,
+			);
+		});
+
+		// Same shenanigans as frame_step so
+		// the passes can see the frame
+		let mut passes : Vec<PostProcessPass> = std::mem::take(&mut self.post_process);
+
+		for (i, pass) in passes.iter_mut().enumerate() {
+			self.frame_buffer = pass(&self.frame());
+
+			//Everything after this indexes the frame buffer by pixel
+			assert_eq!(
+				self.frame_buffer.len(),
+				(self.width() * self.height()) as usize,
+				"Post process pass {i} should give back one color per pixel",
+			);
+		}
+
+		self.post_process = passes;
+	}
+
+	//The last drawn frame, in the form render targets take it
+	pub fn frame(self: &Renderer) -> Frame<'_> {
+		Frame {
+			width : self.width(),
+			height : self.height(),
+			color : &self.frame_buffer,
+			depth : Some(&self.depth_buffer),
+		}
 	}
 
-	pub fn frame_step(self: &mut Renderer<V, TE, P, CE>) -> () {
+	pub fn frame_step(self: &mut Renderer) -> () {
 		//Calling a function that acts on its own struct causes some borrow checker problems, let's
 		//do some shenanigans to please it
-		let mut temp : Option<UpdateFunc<V, TE, P, CE>> = self.update_fn.take();
+		let mut temp : Option<UpdateFunc> = self.update_fn.take();
 
 		if let Some(f) = &mut temp {
-			let f : &mut UpdateFunc<V, TE, P, CE> = f;
+			let f : &mut UpdateFunc = f;
 			(f)(self);
 		}
 
@@ -359,8 +992,139 @@ where
 	}
 }
 
-type UpdateFunc<V, TE, P, CE> =
-	Box<dyn FnMut(&mut Renderer<V, TE, P, CE>) -> ()>;
+pub type UpdateFunc = Box<dyn FnMut(&mut Renderer) -> ()>;
+
+//Bits of sub-pixel precision vertices are snapped to for the edge function rasterizer
+const SUBPIXEL_BITS : u32 = 8;
+
+//Twice the signed area of the triangle from, to, p in fixed point screen coordinates. Zero when
+//p is on the line through from and to, positive when it's on the right of it going from -> to
+//(since screen y goes down)
+fn edge_function(
+	from : I64Vec2,
+	to : I64Vec2,
+	p : I64Vec2,
+) -> i64 {
+	(to.x - from.x) * (p.y - from.y) - (to.y - from.y) * (p.x - from.x)
+}
+
+//Whether the mesh's cull mode throws away a transformed triangle. The winding is worked out from
+//the determinant of the clip space x, y and w, which has the same sign as the on screen area
+//times the sign of each w so it works before clipping even with vertices behind the camera
+fn is_culled<S : Shader>(
+	m : &Mesh<S>,
+	trans_out : &[VertTransOut<S::Varying>; 3],
+) -> bool {
+	if m.cull_mode == CullMode::None {
+		return false;
+	}
+
+	let [a, b, c] : [Vec3; 3] = trans_out.each_ref().map(|v : &VertTransOut<S::Varying>| -> Vec3 {
+		Vec3::new(v.pos.x, v.pos.y, v.pos.w)
+	});
+
+	let signed_area : f32 = Mat3::from_cols(a, b, c).determinant();
+
+	//Edge on triangles don't face either way, leave them for setup to skip
+	if signed_area == 0_f32 {
+		return false;
+	}
+
+	let front_facing : bool = match m.front_face {
+		FrontFace::CounterClockwise => signed_area > 0_f32,
+		FrontFace::Clockwise => signed_area < 0_f32,
+	};
+
+	match m.cull_mode {
+		CullMode::None => false,
+		CullMode::Back => !front_facing,
+		CullMode::Front => front_facing,
+	}
+}
+
+//A clipped triangle with everything the rasterizer needs worked out ahead of time, so it can be
+//drawn into any number of tiles without redoing any of it
+struct SetupTri<P> {
+	//The convention is y_sorted[0] is "a", y_sorted[1] is "b" and y_sorted[2] is "c"
+	y_sorted : [VertTransOut<P>; 3],
+	//Index into y_sorted of the provoking vertex
+	provoking : usize,
+	ndc_verts : [Vec3; 3],
+	screen_y_bounds : [i32; 3],
+	//Vertex positions in screen pixels, snapped to SUBPIXEL_BITS of fixed point
+	fixed_verts : [I64Vec2; 3],
+	//Screen space bounding box, inclusive
+	min : IVec2,
+	max : IVec2,
+	//Converts a point in NDC to the screen space barycentric coords of the triangle
+	bary_mat : Mat3,
+	inv_w : Vec3,
+	ndc_z : Vec3,
+}
+
+impl<P : Interpolate> SetupTri<P> {
+	//The weights of each vertex at a point from its screen space barycentrics
+	fn weights(
+		self: &SetupTri<P>,
+		screen_bary : Vec3,
+	) -> Weights {
+		let persp_bary : Vec3 = screen_bary * self.inv_w;
+
+		Weights {
+			perspective : persp_bary / persp_bary.element_sum(),
+			linear : screen_bary,
+			flat : Vec3::AXES[self.provoking],
+		}
+	}
+
+	fn interpolate(
+		self: &SetupTri<P>,
+		weights : &Weights,
+	) -> P {
+		P::interpolate(
+			[
+				&self.y_sorted[0].varying,
+				&self.y_sorted[1].varying,
+				&self.y_sorted[2].varying,
+			],
+			weights,
+		)
+	}
+
+	//What shaders that don't use derivatives get instead, the same for every pixel
+	fn no_derivatives(self: &SetupTri<P>) -> Derivatives<P> {
+		let zero : P = self.interpolate(&Weights::ZERO);
+
+		Derivatives {
+			ddx : zero,
+			ddy : zero,
+		}
+	}
+}
+
+//Which of a batch's primitives something in a tile's bin is
+#[derive(Debug, Clone, Copy)]
+enum Primitive {
+	Tri(usize),
+	Line(usize),
+	Point(usize),
+}
+
+//All the set up primitives of a mesh along with what's needed to color them
+struct MeshBatch<'a, S : Shader> {
+	tris : Vec<SetupTri<S::Varying>>,
+	lines : Vec<SetupLine<S::Varying>>,
+	points : Vec<SetupPoint<S::Varying>>,
+	shader : &'a S,
+	uniforms : S::Uniforms,
+	depth_state : DepthState,
+	stencil_state : Option<StencilState>,
+	blend_state : Option<BlendState>,
+	//How far the mesh's origin is from the camera, for sorting see through meshes
+	camera_dist : f32,
+	//The lines are a wireframe drawn over the mesh's own triangles
+	overlay : bool,
+}
 
 pub struct RendererSettings {
 	// INTERNAL render width and height - may or may not match up with what the target for
@@ -372,6 +1136,104 @@ pub struct RendererSettings {
 	// Triangles are drawn in 2 phases, set this to true if you want the second phase to have
 	// inverted colors
 	pub show_tri_div : bool,
+	// The screen is split into square tiles this many pixels wide that are rasterized in
+	// parallel
+	pub tile_size : u32,
+	// How many threads rasterize tiles, 1 keeps everything on the thread calling draw
+	pub threads : usize,
+	// Which algorithm turns triangles into pixels
+	pub rasterizer : Rasterizer,
+	// What the depth buffer is reset to before each frame, f32::MAX by default so anything in
+	// front of the far plane passes a less than test. Reversed-Z setups want 0 here
+	pub depth_clear : f32,
+	// What the stencil buffer is reset to before each frame
+	pub stencil_clear : u8,
+	// How meshes with a blend state are put together
+	pub transparency : Transparency,
+	// Multisampling or supersampling for smoother edges, either one always uses the edge
+	// function rasterizer
+	pub anti_aliasing : AntiAliasing,
+	// Draws every mesh with this polygon mode instead of its own when set, handy for seeing
+	// how a whole scene is put together
+	pub polygon_mode : Option<PolygonMode>,
+	// Which vertex of a triangle or line #[flat] varyings are taken from
+	pub provoking_vertex : ProvokingVertex,
+}
+
+impl RendererSettings {
+	// The renderer's conversions between
+	// NDC and pixel coordinates, here so
+	// the raster threads can use them
+	// with just the settings
+	pub fn screen_x_to_ndx(
+		self: &RendererSettings,
+		x : i32,
+	) -> f32 {
+		x as f32 / self.width as f32 * 2_f32 - 1_f32
+	}
+
+	pub fn screen_y_to_ndy(
+		self: &RendererSettings,
+		y : i32,
+	) -> f32 {
+		(1_f32 - (y as f32 / self.height as f32)) * 2_f32 - 1_f32
+	}
+
+	pub fn ndx_to_screen_x(
+		self: &RendererSettings,
+		x : f32,
+	) -> i32 {
+		f32::round(self.width as f32 * ((1_f32 + x) / 2_f32)) as i32
+	}
+
+	pub fn ndy_to_screen_y(
+		self: &RendererSettings,
+		y : f32,
+	) -> i32 {
+		f32::round(self.height as f32 * (1_f32 - ((1_f32 + y) / 2_f32))) as i32
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProvokingVertex {
+	// The convention OpenGL and Vulkan default to
+	First,
+	// Direct3D 9 style
+	Last,
+}
+
+impl ProvokingVertex {
+	// Index of the provoking vertex in a
+	// primitive with this many of them
+	pub fn index(
+		self: ProvokingVertex,
+		vertex_count : usize,
+	) -> usize {
+		match self {
+			ProvokingVertex::First => 0,
+			ProvokingVertex::Last => vertex_count - 1,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transparency {
+	// Blended meshes are drawn furthest first and blended straight away. Cheap, but wrong
+	// wherever see through meshes overlap themselves or intersect each other
+	SortedMeshes,
+	// Every blended pixel is kept in a list for its screen pixel, then each list is sorted by
+	// depth and blended once everything has been drawn, so the order is right per pixel
+	FragmentLists,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rasterizer {
+	// Walks the rows between the vertices, filling from the left edge to the right one
+	Scanline,
+	// Tests every pixel center in the triangle's bounds against its three edges, with the
+	// vertices snapped to sub-pixel fixed point and a top-left fill rule so triangles that share
+	// an edge cover each pixel along it exactly once
+	EdgeFunction,
 }
 
 impl Default for RendererSettings {
@@ -381,6 +1243,15 @@ impl Default for RendererSettings {
 			height : 240 * 2,
 			background_col : Pixel::new(0.5, 0.75, 0.9, 0.5),
 			show_tri_div : false,
+			tile_size : 64,
+			threads : thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get),
+			rasterizer : Rasterizer::Scanline,
+			depth_clear : f32::MAX,
+			stencil_clear : 0,
+			transparency : Transparency::SortedMeshes,
+			anti_aliasing : AntiAliasing::None,
+			polygon_mode : None,
+			provoking_vertex : ProvokingVertex::First,
 		}
 	}
 }
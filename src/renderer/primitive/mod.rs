@@ -0,0 +1,346 @@
+// Lines and points, the other things
+// besides filled triangles that can be
+// drawn into a tile.
+
+use glam::{IVec2, Vec2, Vec3, Vec4Swizzles};
+
+use super::tile::Tile;
+use super::{clipping, MeshBatch, Renderer, RendererSettings, SetupTri};
+use crate::interpolate::{Interpolate, Weights};
+use crate::mesh::{CompareFunc, Derivatives, VertTransOut};
+use crate::pixel::Pixel;
+use crate::shader::Shader;
+
+//How far (in NDC depth) wireframe overlays are pulled towards the camera so they win the depth
+//test against the triangles they're drawn on, on top of however much the triangle's depth
+//changes over a pixel
+const OVERLAY_DEPTH_BIAS : f32 = 1e-5;
+
+//A clipped line ready to be drawn into any number of tiles
+pub struct SetupLine<P> {
+	//Both ends have the same #[flat] fields
+	verts : [VertTransOut<P>; 2],
+	//End positions in screen pixels
+	screen_verts : [Vec2; 2],
+	inv_w : [f32; 2],
+	ndc_z : [f32; 2],
+	//Pixels up to this far either side of the line (along whichever axis it covers less of)
+	//are drawn
+	half_width : f32,
+	//How far the line is pulled towards the camera, only overlays have any
+	pub depth_bias : f32,
+	//Screen space bounding box, inclusive
+	pub min : IVec2,
+	pub max : IVec2,
+}
+
+//A point inside the view volume and the square of pixels it covers
+pub struct SetupPoint<P> {
+	vert : VertTransOut<P>,
+	ndc_z : f32,
+	//The square's corner pixels, inclusive and cut down to the screen
+	pub min : IVec2,
+	pub max : IVec2,
+}
+
+impl Renderer {
+	fn ndc_to_screen_pos(
+		self: &Renderer,
+		ndc : Vec3,
+	) -> Vec2 {
+		Vec2::new(
+			self.width() as f32 * ((1_f32 + ndc.x) / 2_f32),
+			self.height() as f32 * (1_f32 - ((1_f32 + ndc.y) / 2_f32)),
+		)
+	}
+
+	fn clamp_to_screen(
+		self: &Renderer,
+		p : IVec2,
+	) -> IVec2 {
+		p.clamp(
+			IVec2::ZERO,
+			IVec2::new(self.width() as i32 - 1, self.height() as i32 - 1),
+		)
+	}
+
+	//The first and last pixels with their centers within half_size of pos, pixels are half open
+	//so a size of 1 always covers exactly one
+	fn pixel_span(
+		pos : f32,
+		half_size : f32,
+	) -> (i32, i32) {
+		(
+			(pos - half_size - 0.5).floor() as i32 + 1,
+			(pos + half_size - 0.5).floor() as i32,
+		)
+	}
+
+	// How much an overlay drawn on a
+	// triangle has to be pulled in by
+	pub(super) fn overlay_depth_bias<P>(
+		self: &Renderer,
+		tri : &SetupTri<P>,
+	) -> f32 {
+		//How much the depth changes going across the triangle in NDC, then per pixel. Lines and
+		//triangles don't sample depth at exactly the same spots, so a steep triangle can be a
+		//fair bit in front of its own edges
+		let gradient : Vec3 = tri.bary_mat.transpose() * tri.ndc_z;
+
+		OVERLAY_DEPTH_BIAS
+			+ gradient.x.abs() * 2_f32 / self.width() as f32
+			+ gradient.y.abs() * 2_f32 / self.height() as f32
+	}
+
+	// Clip a transformed line and set up
+	// whatever is left of it, with the
+	// #[flat] fields of provoking
+	pub(super) fn setup_line<P : Interpolate>(
+		self: &Renderer,
+		trans_out : [VertTransOut<P>; 2],
+		provoking : &P,
+		width : f32,
+		depth_bias : f32,
+		out : &mut Vec<SetupLine<P>>,
+	) -> () {
+		let trans_out : [VertTransOut<P>; 2] =
+			trans_out.map(|v : VertTransOut<P>| -> VertTransOut<P> {
+				clipping::with_flat_of(&v, provoking)
+			});
+
+		let Some(verts) = clipping::clip_line(trans_out) else {
+			return;
+		};
+
+		let ndc_verts : [Vec3; 2] =
+			verts.map(|v : VertTransOut<P>| -> Vec3 { v.pos.xyz() / v.pos.w });
+
+		let screen_verts : [Vec2; 2] =
+			ndc_verts.map(|v : Vec3| -> Vec2 { self.ndc_to_screen_pos(v) });
+
+		let half_width : f32 = width.max(1_f32) / 2_f32;
+
+		out.push(SetupLine {
+			verts,
+			screen_verts,
+			inv_w : verts.map(|v : VertTransOut<P>| -> f32 { 1_f32 / v.pos.w }),
+			ndc_z : ndc_verts.map(|v : Vec3| -> f32 { v.z }),
+			half_width,
+			depth_bias,
+			min : self.clamp_to_screen(
+				(screen_verts[0].min(screen_verts[1]) - half_width).floor().as_ivec2(),
+			),
+			max : self.clamp_to_screen(
+				(screen_verts[0].max(screen_verts[1]) + half_width).floor().as_ivec2(),
+			),
+		});
+	}
+
+	// Set up a transformed point if it is
+	// inside the view volume, with the
+	// #[flat] fields of provoking
+	pub(super) fn setup_point<P : Interpolate>(
+		self: &Renderer,
+		vert : VertTransOut<P>,
+		provoking : &P,
+		size : f32,
+		out : &mut Vec<SetupPoint<P>>,
+	) -> () {
+		//Like on GPUs, big points pop out of view all at once when their center leaves it
+		if !clipping::point_visible(&vert) {
+			return;
+		}
+
+		let vert : VertTransOut<P> = clipping::with_flat_of(&vert, provoking);
+
+		let ndc : Vec3 = vert.pos.xyz() / vert.pos.w;
+
+		let screen_pos : Vec2 = self.ndc_to_screen_pos(ndc);
+		let half_size : f32 = size.max(1_f32) / 2_f32;
+
+		let (min_x, max_x) : (i32, i32) = Self::pixel_span(screen_pos.x, half_size);
+		let (min_y, max_y) : (i32, i32) = Self::pixel_span(screen_pos.y, half_size);
+
+		out.push(SetupPoint {
+			vert,
+			ndc_z : ndc.z,
+			min : self.clamp_to_screen(IVec2::new(min_x, min_y)),
+			max : self.clamp_to_screen(IVec2::new(max_x, max_y)),
+		});
+	}
+
+	// Draw the part of a set up line inside
+	// of a tile, a run of pixels across it
+	// for every column (or row for steep
+	// lines) it crosses
+	pub(super) fn raster_line<S : Shader>(
+		settings : &RendererSettings,
+		line : &SetupLine<S::Varying>,
+		batch : &MeshBatch<S>,
+		tile : &mut Tile,
+	) -> () {
+		let [start, end] : [Vec2; 2] = line.screen_verts;
+		let delta : Vec2 = end - start;
+
+		//Steps are taken along whichever axis the line covers more of
+		let major : usize = if delta.x.abs() >= delta.y.abs() { 0 } else { 1 };
+		let minor : usize = 1 - major;
+
+		let min : IVec2 = line.min.max(tile.min);
+		let max : IVec2 = line.max.min(tile.max);
+
+		//Thick lines only spread out sideways, the ends are where they'd be for a thin one
+		let first_step : i32 = (start[major].min(end[major]).floor() as i32).max(min[major]);
+		let last_step : i32 = (start[major].max(end[major]).floor() as i32).min(max[major]);
+
+		//Weights of the ends a fraction t of the way along the line on screen
+		let weights = |t : f32| -> Weights {
+			let a : f32 = (1_f32 - t) * line.inv_w[0];
+			let b : f32 = t * line.inv_w[1];
+
+			Weights {
+				perspective : Vec3::new(a / (a + b), b / (a + b), 0_f32),
+				linear : Vec3::new(1_f32 - t, t, 0_f32),
+				flat : Vec3::X,
+			}
+		};
+
+		let interpolate = |weights : &Weights| -> S::Varying {
+			Interpolate::interpolate(
+				[&line.verts[0].varying, &line.verts[1].varying, &line.verts[1].varying],
+				weights,
+			)
+		};
+
+		//How far t moves going one pixel along the line
+		let t_step : f32 = 1_f32 / delta[major].abs().max(1_f32);
+
+		for step in first_step..=last_step {
+			//Where the line crosses the middle of this column
+			let t : f32 = if delta[major] == 0_f32 {
+				0_f32
+			} else {
+				((step as f32 + 0.5 - start[major]) / delta[major]).clamp(0_f32, 1_f32)
+			};
+
+			let (first_across, last_across) : (i32, i32) =
+				Self::pixel_span(start[minor] + delta[minor] * t, line.half_width);
+
+			let z : f32 = <f32 as glam::FloatExt>::lerp(line.ndc_z[0], line.ndc_z[1], t);
+
+			//Every pixel across the line gets the same varying
+			let shade = || -> Pixel {
+				let p : S::Varying = interpolate(&weights(t));
+
+				//Lines only change along their length, so both are the change one pixel along it
+				let along : S::Varying = if S::USES_DERIVATIVES {
+					interpolate(&(weights((t + t_step).min(1_f32)) - weights(t)))
+				} else {
+					interpolate(&Weights::ZERO)
+				};
+
+				batch.shader.color_pixel(
+					&p,
+					&Derivatives {
+						ddx : along,
+						ddy : along,
+					},
+					&batch.uniforms,
+				)
+			};
+
+			for across in first_across.max(min[minor])..=last_across.min(max[minor]) {
+				let mut pixel : IVec2 = IVec2::ZERO;
+
+				pixel[major] = step;
+				pixel[minor] = across;
+
+				Self::write_covered_pixel(settings, batch, tile, pixel, z, line.depth_bias, shade);
+			}
+		}
+	}
+
+	// Draw the part of a set up point's
+	// square inside of a tile
+	pub(super) fn raster_point<S : Shader>(
+		settings : &RendererSettings,
+		point : &SetupPoint<S::Varying>,
+		batch : &MeshBatch<S>,
+		tile : &mut Tile,
+	) -> () {
+		let min : IVec2 = point.min.max(tile.min);
+		let max : IVec2 = point.max.min(tile.max);
+
+		//The whole square is one color
+		let shade = || -> Pixel {
+			let p : S::Varying = point.vert.varying;
+
+			batch.shader.color_pixel(
+				&p,
+				&Derivatives {
+					ddx : Interpolate::interpolate([&p, &p, &p], &Weights::ZERO),
+					ddy : Interpolate::interpolate([&p, &p, &p], &Weights::ZERO),
+				},
+				&batch.uniforms,
+			)
+		};
+
+		for y in min.y..=max.y {
+			for x in min.x..=max.x {
+				Self::write_covered_pixel(
+					settings,
+					batch,
+					tile,
+					IVec2::new(x, y),
+					point.ndc_z,
+					0_f32,
+					shade,
+				);
+			}
+		}
+	}
+
+	// Depth test and write every sample of
+	// a pixel a line or point covers, the
+	// shader only runs once and only if
+	// one of them passes
+	fn write_covered_pixel<S : Shader>(
+		settings : &RendererSettings,
+		batch : &MeshBatch<S>,
+		tile : &mut Tile,
+		pixel : IVec2,
+		z : f32,
+		depth_bias : f32,
+		shade : impl Fn() -> Pixel + Copy,
+	) -> () {
+		//Towards the camera is whichever way wins the depth test
+		let z : f32 = match batch.depth_state.compare {
+			CompareFunc::Greater | CompareFunc::GreaterEqual => z + depth_bias,
+			_ => z - depth_bias,
+		};
+
+		let pixel_idx : usize = tile.idx(pixel.x, pixel.y);
+
+		let mut fill : Option<Pixel> = None;
+
+		for s in 0..tile.samples {
+			let sample_idx : usize = pixel_idx * tile.samples + s;
+
+			if !Self::depth_stencil_test(batch, tile, sample_idx, z) {
+				continue;
+			}
+
+			let fill : Pixel = *fill.get_or_insert_with(|| -> Pixel {
+				let fill : Pixel = shade();
+
+				if batch.overlay {
+					(Vec3::ONE - fill.truncate()).extend(fill.w)
+				} else {
+					fill
+				}
+			});
+
+			Self::write_pixel(settings, batch, tile, sample_idx, fill, z, false);
+		}
+	}
+}
@@ -0,0 +1,141 @@
+// Screen tiles that get rasterized
+// independently of each other, each one
+// on whatever thread picks it up.
+
+use glam::IVec2;
+
+use crate::blend::BlendState;
+use crate::pixel::Pixel;
+
+//A rectangle of the screen with its own color and depth buffers, so threads working on
+//different tiles never touch the same memory
+pub struct Tile {
+	//Top left pixel of the tile, in screen coordinates
+	pub min : IVec2,
+	//Bottom right pixel of the tile, inclusive
+	pub max : IVec2,
+	//How many samples each pixel has, they're stored next to each other in the buffers
+	pub samples : usize,
+	//Row major like the renderer's buffers, but with an entry per sample instead of per pixel
+	pub color : Vec<Pixel>,
+	pub depth : Vec<f32>,
+	pub stencil : Vec<u8>,
+	//Blended samples waiting to be sorted and resolved, one list per sample. Empty unless the
+	//tile was made for Transparency::FragmentLists
+	pub fragments : Vec<Vec<Fragment>>,
+}
+
+//A see through pixel that hasn't been blended in yet
+#[derive(Debug, Clone, Copy)]
+pub struct Fragment {
+	pub color : Pixel,
+	pub depth : f32,
+	pub blend_state : BlendState,
+}
+
+impl Tile {
+	pub fn new(
+		min : IVec2,
+		max : IVec2,
+		samples : usize,
+		clear_color : Pixel,
+		clear_depth : f32,
+		clear_stencil : u8,
+		fragment_lists : bool,
+	) -> Tile {
+		let area : usize = ((max.x - min.x + 1) * (max.y - min.y + 1)) as usize * samples;
+
+		Tile {
+			min,
+			max,
+			samples,
+			color : vec![clear_color; area],
+			depth : vec![clear_depth; area],
+			stencil : vec![clear_stencil; area],
+			fragments : if fragment_lists {
+				vec![Vec::new(); area]
+			} else {
+				Vec::new()
+			},
+		}
+	}
+
+	pub fn width(self: &Tile) -> i32 {
+		self.max.x - self.min.x + 1
+	}
+
+	//Index of a pixel given in screen coordinates, the index into the tile's buffers of its
+	//first sample is this times samples
+	pub fn idx(
+		self: &Tile,
+		x : i32,
+		y : i32,
+	) -> usize {
+		((y - self.min.y) * self.width() + x - self.min.x) as usize
+	}
+
+	//Blends every sample's fragments into its color, furthest first. They were already depth
+	//tested against the opaque meshes, which are always drawn before blended ones
+	pub fn resolve_fragments(self: &mut Tile) -> () {
+		self.fragments
+			.iter_mut()
+			.enumerate()
+			.for_each(|(sample_idx, list) : (usize, &mut Vec<Fragment>)| -> () {
+				list.sort_by(|a : &Fragment, b : &Fragment| -> std::cmp::Ordering {
+					b.depth.total_cmp(&a.depth)
+				});
+
+				list.drain(..).for_each(|f : Fragment| -> () {
+					self.color[sample_idx] = f.blend_state.blend(f.color, self.color[sample_idx]);
+				});
+			});
+	}
+
+	//Copies the tile's buffers back into full screen ones that are screen_width wide. Each pixel's
+	//color is the average of its samples, depth and stencil just come from the first sample
+	pub fn write_back(
+		self: &Tile,
+		screen_width : i32,
+		color : &mut [Pixel],
+		depth : &mut [f32],
+		stencil : &mut [u8],
+	) -> () {
+		for y in self.min.y..=self.max.y {
+			for x in self.min.x..=self.max.x {
+				let src : usize = self.idx(x, y) * self.samples;
+				let dst : usize = (y * screen_width + x) as usize;
+
+				color[dst] = self.color[src..src + self.samples].iter().sum::<Pixel>()
+					/ self.samples as f32;
+				depth[dst] = self.depth[src];
+				stencil[dst] = self.stencil[src];
+			}
+		}
+	}
+}
+
+//Rectangles (min and max pixel, inclusive) covering a width by height screen, tile_size pixels
+//on a side apart from the ones on the right and bottom edges which get whatever's left
+pub fn tile_rects(
+	width : u32,
+	height : u32,
+	tile_size : u32,
+) -> Vec<(IVec2, IVec2)> {
+	let tile_size : u32 = tile_size.max(1);
+
+	(0..height.div_ceil(tile_size))
+		.flat_map(|ty : u32| -> Vec<(IVec2, IVec2)> {
+			(0..width.div_ceil(tile_size))
+				.map(|tx : u32| -> (IVec2, IVec2) {
+					let min : IVec2 = IVec2::new((tx * tile_size) as i32, (ty * tile_size) as i32);
+					let max : IVec2 = IVec2::new(
+						(((tx + 1) * tile_size).min(width) - 1) as i32,
+						(((ty + 1) * tile_size).min(height) - 1) as i32,
+					);
+
+					(min, max)
+				})
+				.collect()
+		})
+		.collect()
+}
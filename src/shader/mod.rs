@@ -0,0 +1,198 @@
+// What a mesh runs on its vertices and
+// pixels, along with the shaders that
+// come with the renderer.
+
+use std::sync::{Arc, OnceLock};
+
+use glam::{Mat4, Vec2, Vec3, Vec4};
+
+use crate::interpolate::Interpolate;
+use crate::mesh::{Derivatives, Mesh, VertTransOut};
+use crate::pixel::Pixel;
+use crate::renderer::Renderer;
+use crate::texture::{ColorSpace, Texture};
+
+//Everything that decides how a mesh looks. The shader is a value that lives on the mesh, so it can
+//hold on to whatever it likes (textures, colors, lights...) and every mesh can have its own.
+//It has to be Sync since every raster thread colors pixels with it at the same time, and 'static
+//so the renderer can hand back meshes as the type they really are
+pub trait Shader : Sized + Sync + 'static {
+	//The vertex data a mesh's geometry is made of, probably contains a Vec3 position
+	type Vertex : Clone + Copy + Sync;
+	//Made by transform_vertex, interpolated across each primitive and handed to color_pixel
+	type Varying : Interpolate;
+	//Worked out once per mesh per draw and shared by every vertex and pixel, like the
+	//projection-camera-model matrix
+	type Uniforms : Sync;
+
+	//Whether color_pixel looks at its derivatives. Working them out takes two more
+	//interpolations for every pixel, so shaders that don't get zeros instead
+	const USES_DERIVATIVES : bool = false;
+
+	fn uniforms(
+		&self,
+		mesh : &Mesh<Self>,
+		renderer : &Renderer,
+	) -> Self::Uniforms;
+
+	fn transform_vertex(
+		&self,
+		vertex : &Self::Vertex,
+		uniforms : &Self::Uniforms,
+	) -> VertTransOut<Self::Varying>;
+
+	fn color_pixel(
+		&self,
+		varying : &Self::Varying,
+		derivs : &Derivatives<Self::Varying>,
+		uniforms : &Self::Uniforms,
+	) -> Pixel;
+}
+
+//The matrices most shaders need to get a vertex into clip space
+#[derive(Clone, Copy, Debug)]
+pub struct BasicUniforms {
+	pub p_mat : Mat4,
+	pub cm_mat : Mat4,
+}
+
+impl BasicUniforms {
+	pub fn new<S : Shader>(
+		mesh : &Mesh<S>,
+		renderer : &Renderer,
+	) -> BasicUniforms {
+		BasicUniforms {
+			//Kinda sucks that proj * camera has to be computed once per mesh now :/
+			p_mat : renderer.camera.proj_mat,
+			cm_mat : renderer.camera.camera_mat * mesh.model_mat,
+		}
+	}
+
+	pub fn clip_pos(
+		self: &BasicUniforms,
+		position : Vec3,
+	) -> Vec4 {
+		self.p_mat * self.cm_mat * Vec4::from((position, 1_f32))
+	}
+}
+
+//Colors each vertex and blends between them
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BasicShader;
+
+#[derive(Clone, Copy, Debug)]
+pub struct BasicV {
+	pub position : Vec3,
+	pub color : Vec3,
+}
+
+#[derive(Clone, Copy, Debug, Interpolate)]
+pub struct BasicP {
+	pub color : Vec3,
+}
+
+impl Shader for BasicShader {
+	type Vertex = BasicV;
+	type Varying = BasicP;
+	type Uniforms = BasicUniforms;
+
+	fn uniforms(
+		self: &BasicShader,
+		mesh : &Mesh<BasicShader>,
+		renderer : &Renderer,
+	) -> BasicUniforms {
+		BasicUniforms::new(mesh, renderer)
+	}
+
+	fn transform_vertex(
+		self: &BasicShader,
+		vertex : &BasicV,
+		uniforms : &BasicUniforms,
+	) -> VertTransOut<BasicP> {
+		VertTransOut {
+			pos : uniforms.clip_pos(vertex.position),
+			varying : BasicP {
+				color : vertex.color,
+			},
+		}
+	}
+
+	fn color_pixel(
+		self: &BasicShader,
+		varying : &BasicP,
+		_derivs : &Derivatives<BasicP>,
+		_uniforms : &BasicUniforms,
+	) -> Pixel {
+		Vec4::from((varying.color, 1.0))
+	}
+}
+
+//Colors meshes with a texture. Textures can be big, so they are shared instead of copied
+#[derive(Clone)]
+pub struct TexturedShader {
+	pub texture : Arc<Texture>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TexturedV {
+	pub position : Vec3,
+	pub tex_coord : Vec2,
+}
+
+#[derive(Clone, Copy, Debug, Interpolate)]
+pub struct TexturedP {
+	pub tex_coord : Vec2,
+}
+
+impl Shader for TexturedShader {
+	type Vertex = TexturedV;
+	type Varying = TexturedP;
+	type Uniforms = BasicUniforms;
+
+	//Mip levels are picked from them
+	const USES_DERIVATIVES : bool = true;
+
+	fn uniforms(
+		self: &TexturedShader,
+		mesh : &Mesh<TexturedShader>,
+		renderer : &Renderer,
+	) -> BasicUniforms {
+		BasicUniforms::new(mesh, renderer)
+	}
+
+	fn transform_vertex(
+		self: &TexturedShader,
+		vertex : &TexturedV,
+		uniforms : &BasicUniforms,
+	) -> VertTransOut<TexturedP> {
+		VertTransOut {
+			pos : uniforms.clip_pos(vertex.position),
+			varying : TexturedP {
+				tex_coord : vertex.tex_coord,
+			},
+		}
+	}
+
+	fn color_pixel(
+		self: &TexturedShader,
+		varying : &TexturedP,
+		derivs : &Derivatives<TexturedP>,
+		_uniforms : &BasicUniforms,
+	) -> Pixel {
+		self.texture
+			.sample_grad(varying.tex_coord, derivs.ddx.tex_coord, derivs.ddy.tex_coord)
+	}
+}
+
+//The icon, decoded the first time it's asked for and shared from then on. Its colors are kept as
+//they are since nothing the demo draws is gamma corrected
+static DEMO_TEXTURE : OnceLock<Arc<Texture>> = OnceLock::new();
+
+pub fn demo_texture() -> Arc<Texture> {
+	Arc::clone(DEMO_TEXTURE.get_or_init(|| -> Arc<Texture> {
+		Arc::new(
+			Texture::load_from_memory(include_bytes!("../../icon.png"), ColorSpace::Linear)
+				.expect("Icon should be loadable!"),
+		)
+	}))
+}
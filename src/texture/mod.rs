@@ -0,0 +1,471 @@
+// Images that can be sampled from while
+// coloring pixels.
+
+use std::path::Path;
+
+use glam::{Vec2, Vec4};
+use image::{DynamicImage, Rgba32FImage};
+
+use crate::pixel::Pixel;
+
+//How a sample in between texel centers is worked out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filter {
+	//The closest texel
+	Nearest,
+	//Weighted average of the 4 closest texels
+	Bilinear,
+	//Bilinear samples from the two mip levels closest to the texture's size on screen, blended
+	//together. Needs the texture coordinate derivatives to pick the levels, see sample_grad
+	Trilinear,
+	//Up to this many trilinear samples spread along the direction the texture is squashed the
+	//most in, for surfaces seen at a steep angle
+	Anisotropic(u32),
+}
+
+//What happens to texture coordinates outside of 0.0..=1.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WrapMode {
+	//Tile the texture
+	Repeat,
+	//Stretch out the edge texels
+	Clamp,
+	//Tile the texture, flipping every other tile
+	Mirror,
+}
+
+//How the colors in an image file are stored
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+	//Gamma encoded, like nearly every color image. Decoded to linear so filtering and lighting
+	//work with actual amounts of light, which means the frame needs a gamma pass at the end
+	Srgb,
+	//Used as is, for data like normal maps, or color images drawn into frames that aren't gamma
+	//corrected either
+	Linear,
+}
+
+//A single level of the mip chain
+#[derive(Clone, Debug)]
+struct MipLevel {
+	width : u32,
+	height : u32,
+	//Row major, top row first
+	texels : Vec<Pixel>,
+}
+
+impl MipLevel {
+	fn texel(
+		self: &MipLevel,
+		x : i32,
+		y : i32,
+		wrap : WrapMode,
+	) -> Pixel {
+		let x : u32 = wrap_coord(x, self.width, wrap);
+		let y : u32 = wrap_coord(y, self.height, wrap);
+
+		self.texels[(y * self.width + x) as usize]
+	}
+
+	//Every texel is the average of the 2x2 block under it in this level. Odd sizes round down,
+	//with the wrap mode deciding what the missing texels are
+	fn downsample(
+		self: &MipLevel,
+		wrap : WrapMode,
+	) -> MipLevel {
+		let width : u32 = (self.width / 2).max(1);
+		let height : u32 = (self.height / 2).max(1);
+
+		let texels : Vec<Pixel> = (0..height as i32)
+			.flat_map(|y : i32| -> Vec<Pixel> {
+				(0..width as i32)
+					.map(|x : i32| -> Pixel {
+						(self.texel(x * 2, y * 2, wrap)
+							+ self.texel(x * 2 + 1, y * 2, wrap)
+							+ self.texel(x * 2, y * 2 + 1, wrap)
+							+ self.texel(x * 2 + 1, y * 2 + 1, wrap))
+							* 0.25_f32
+					})
+					.collect()
+			})
+			.collect();
+
+		MipLevel {
+			width,
+			height,
+			texels,
+		}
+	}
+
+	fn sample_nearest(
+		self: &MipLevel,
+		uv : Vec2,
+		wrap : WrapMode,
+	) -> Pixel {
+		let (x, y) : (f32, f32) = self.uv_to_texel_space(uv);
+
+		self.texel(x.floor() as i32, y.floor() as i32, wrap)
+	}
+
+	fn sample_bilinear(
+		self: &MipLevel,
+		uv : Vec2,
+		wrap : WrapMode,
+	) -> Pixel {
+		let (x, y) : (f32, f32) = self.uv_to_texel_space(uv);
+
+		//Texel centers are at +0.5
+		let x : f32 = x - 0.5_f32;
+		let y : f32 = y - 0.5_f32;
+
+		let x0 : i32 = x.floor() as i32;
+		let y0 : i32 = y.floor() as i32;
+
+		let tx : f32 = x - x.floor();
+		let ty : f32 = y - y.floor();
+
+		let top : Pixel = self.texel(x0, y0, wrap).lerp(self.texel(x0 + 1, y0, wrap), tx);
+		let bot : Pixel =
+			self.texel(x0, y0 + 1, wrap).lerp(self.texel(x0 + 1, y0 + 1, wrap), tx);
+
+		top.lerp(bot, ty)
+	}
+
+	//Texel space has its origin in the top left corner, while texture coordinates have theirs
+	//in the bottom left
+	fn uv_to_texel_space(
+		self: &MipLevel,
+		uv : Vec2,
+	) -> (f32, f32) {
+		(uv.x * self.width as f32, (1_f32 - uv.y) * self.height as f32)
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Texture {
+	//Full size image first, then each level half the size of the last, down to 1x1. Images
+	//loaded as ColorSpace::Srgb have been decoded to linear by now
+	mips : Vec<MipLevel>,
+	pub filter : Filter,
+	pub wrap : WrapMode,
+}
+
+impl Texture {
+	//Texels are row major, top row first. The mip chain is built right away
+	pub fn new(
+		width : u32,
+		height : u32,
+		texels : Vec<Pixel>,
+	) -> Texture {
+		assert!(width > 0 && height > 0, "Textures can't be empty");
+		assert_eq!(
+			texels.len(),
+			(width * height) as usize,
+			"Texture should have exactly width * height texels"
+		);
+
+		let mut ret : Texture = Texture {
+			mips : vec![MipLevel {
+				width,
+				height,
+				texels,
+			}],
+			filter : Filter::Bilinear,
+			wrap : WrapMode::Repeat,
+		};
+
+		ret.generate_mips();
+
+		ret
+	}
+
+	//Alpha is always linear, only the colors are decoded
+	pub fn from_image(
+		image : &DynamicImage,
+		color_space : ColorSpace,
+	) -> Texture {
+		let rgba : Rgba32FImage = image.to_rgba32f();
+
+		Texture::new(
+			rgba.width(),
+			rgba.height(),
+			rgba.pixels()
+				.map(|p : &image::Rgba<f32>| -> Pixel {
+					let p : Pixel = Vec4::from_array(p.0);
+
+					match color_space {
+						ColorSpace::Srgb => p.truncate().map(srgb_to_linear).extend(p.w),
+						ColorSpace::Linear => p,
+					}
+				})
+				.collect(),
+		)
+	}
+
+	pub fn load(
+		path : &Path,
+		color_space : ColorSpace,
+	) -> Result<Texture, String> {
+		image::open(path)
+			.map(|i : DynamicImage| -> Texture { Texture::from_image(&i, color_space) })
+			.map_err(|e : image::ImageError| -> String {
+				format!("Could not load texture {}: {}", path.display(), e)
+			})
+	}
+
+	pub fn load_from_memory(
+		bytes : &[u8],
+		color_space : ColorSpace,
+	) -> Result<Texture, String> {
+		image::load_from_memory(bytes)
+			.map(|i : DynamicImage| -> Texture { Texture::from_image(&i, color_space) })
+			.map_err(|e : image::ImageError| -> String { e.to_string() })
+	}
+
+	//Rebuilds the mip chain from the full size image. Mips are averaged with the wrap mode the
+	//texture has right now, so call this again after changing it if the edges matter
+	pub fn generate_mips(self: &mut Texture) -> () {
+		self.mips.truncate(1);
+
+		while self.mips.last().is_some_and(|m : &MipLevel| -> bool { m.width > 1 || m.height > 1 })
+		{
+			let next : MipLevel = self.mips[self.mips.len() - 1].downsample(self.wrap);
+
+			self.mips.push(next);
+		}
+	}
+
+	pub fn width(self: &Texture) -> u32 {
+		self.mips[0].width
+	}
+
+	pub fn height(self: &Texture) -> u32 {
+		self.mips[0].height
+	}
+
+	pub fn mip_count(self: &Texture) -> usize {
+		self.mips.len()
+	}
+
+	//Texel lookup in the full size image with the wrap mode applied, so any coordinates are fine
+	pub fn texel(
+		self: &Texture,
+		x : i32,
+		y : i32,
+	) -> Pixel {
+		self.mips[0].texel(x, y, self.wrap)
+	}
+
+	//Samples the full size image with the texture's filter and wrap mode, mip mapping filters
+	//fall back to bilinear since there's no way to know which level to use. (0, 0) is the
+	//bottom left corner and (1, 1) the top right, the same way OBJ files lay out their texture
+	//coordinates
+	pub fn sample(
+		self: &Texture,
+		uv : Vec2,
+	) -> Pixel {
+		match self.filter {
+			Filter::Nearest => self.mips[0].sample_nearest(uv, self.wrap),
+			_ => self.mips[0].sample_bilinear(uv, self.wrap),
+		}
+	}
+
+	//Like sample, but with how much the texture coordinates change from one pixel to the next
+	//horizontally (duv_dx) and vertically (duv_dy), which is what the mip mapping filters use to
+	//pick their levels. These come straight out of the varying's Derivatives
+	pub fn sample_grad(
+		self: &Texture,
+		uv : Vec2,
+		duv_dx : Vec2,
+		duv_dy : Vec2,
+	) -> Pixel {
+		let size : Vec2 = Vec2::new(self.width() as f32, self.height() as f32);
+
+		//Footprint of the pixel in texels
+		let texels_dx : Vec2 = duv_dx * size;
+		let texels_dy : Vec2 = duv_dy * size;
+
+		match self.filter {
+			Filter::Nearest | Filter::Bilinear => self.sample(uv),
+			Filter::Trilinear => {
+				let lod : f32 = texels_dx.length().max(texels_dy.length()).log2();
+
+				self.sample_lod(uv, lod)
+			},
+			Filter::Anisotropic(max_samples) => {
+				let (major, minor) : (Vec2, f32) = if texels_dx.length() >= texels_dy.length() {
+					(duv_dx, texels_dy.length())
+				} else {
+					(duv_dy, texels_dx.length())
+				};
+
+				let major_len : f32 = (major * size).length();
+
+				//Each sample covers a roughly square part of the footprint, so the level is
+				//picked from the short side instead of the long one
+				let sample_count : u32 = ((major_len / minor.max(f32::MIN_POSITIVE)).ceil() as u32)
+					.clamp(1, max_samples.max(1));
+
+				let lod : f32 = (major_len / sample_count as f32).log2();
+
+				(0..sample_count)
+					.map(|i : u32| -> Pixel {
+						let offset : f32 = (i as f32 + 0.5_f32) / sample_count as f32 - 0.5_f32;
+
+						self.sample_lod(uv + major * offset, lod)
+					})
+					.sum::<Pixel>()
+					/ sample_count as f32
+			},
+		}
+	}
+
+	//Blend of bilinear samples from the two levels around lod, where lod 0 is the full size
+	//image and every level up is half the size
+	pub fn sample_lod(
+		self: &Texture,
+		uv : Vec2,
+		lod : f32,
+	) -> Pixel {
+		//NaN from zero sized footprints goes to the full size image too
+		let lod : f32 = if lod.is_nan() {
+			0_f32
+		} else {
+			lod.clamp(0_f32, (self.mips.len() - 1) as f32)
+		};
+
+		let lower : usize = lod.floor() as usize;
+		let upper : usize = (lower + 1).min(self.mips.len() - 1);
+
+		self.mips[lower]
+			.sample_bilinear(uv, self.wrap)
+			.lerp(self.mips[upper].sample_bilinear(uv, self.wrap), lod - lod.floor())
+	}
+}
+
+//The piecewise sRGB curve, a short straight bit near black and a 2.4 power everywhere else
+fn srgb_to_linear(c : f32) -> f32 {
+	if c <= 0.04045 {
+		c / 12.92
+	} else {
+		((c + 0.055) / 1.055).powf(2.4)
+	}
+}
+
+fn wrap_coord(
+	c : i32,
+	size : u32,
+	wrap : WrapMode,
+) -> u32 {
+	let size : i32 = size as i32;
+
+	(match wrap {
+		WrapMode::Repeat => c.rem_euclid(size),
+		WrapMode::Clamp => c.clamp(0, size - 1),
+		WrapMode::Mirror => {
+			let c : i32 = c.rem_euclid(size * 2);
+
+			if c < size { c } else { size * 2 - 1 - c }
+		},
+	}) as u32
+}
+
+#[cfg(test)]
+mod tests {
+	use glam::{Vec2, Vec4};
+	use image::{DynamicImage, Rgba, RgbaImage};
+
+	use super::{wrap_coord, ColorSpace, Filter, MipLevel, Texture, WrapMode};
+
+	//Where -5..9 end up in a texture 4 texels wide
+	#[test]
+	fn wrap_coords() {
+		let expected : [(WrapMode, [u32; 14]); 3] = [
+			(WrapMode::Repeat, [3, 0, 1, 2, 3, 0, 1, 2, 3, 0, 1, 2, 3, 0]),
+			(WrapMode::Clamp, [0, 0, 0, 0, 0, 0, 1, 2, 3, 3, 3, 3, 3, 3]),
+			(WrapMode::Mirror, [3, 3, 2, 1, 0, 0, 1, 2, 3, 3, 2, 1, 0, 0]),
+		];
+
+		for (wrap, wrapped) in expected {
+			assert_eq!(
+				std::array::from_fn(|i : usize| -> u32 { wrap_coord(i as i32 - 5, 4, wrap) }),
+				wrapped,
+				"{:?}",
+				wrap
+			);
+		}
+	}
+
+	//Every level is filled with its own shade of gray so it's easy to tell which ones got used
+	#[test]
+	fn sample_lod_levels() {
+		let mut texture : Texture = Texture::new(4, 4, vec![Vec4::ZERO; 16]);
+
+		assert_eq!(texture.mip_count(), 3);
+
+		texture.mips.iter_mut().enumerate().for_each(|(i, m) : (usize, &mut MipLevel)| -> () {
+			m.texels.fill(Vec4::splat(i as f32 * 0.5));
+		});
+
+		let expected : [(f32, f32); 8] = [
+			(0.0, 0.0),
+			(0.5, 0.25),
+			(1.0, 0.5),
+			(1.5, 0.75),
+			(2.0, 1.0),
+			//Past either end of the chain sticks to the last level there is
+			(-1.0, 0.0),
+			(5.0, 1.0),
+			(f32::NAN, 0.0),
+		];
+
+		for (lod, gray) in expected {
+			assert_eq!(
+				texture.sample_lod(Vec2::new(0.3, 0.6), lod),
+				Vec4::splat(gray),
+				"lod {}",
+				lod
+			);
+		}
+
+		//Trilinear takes the level from the longer side of the pixel's footprint, 2 texels here
+		texture.filter = Filter::Trilinear;
+
+		assert_eq!(
+			texture.sample_grad(Vec2::new(0.3, 0.6), Vec2::new(0.5, 0.0), Vec2::new(0.0, 0.25)),
+			Vec4::splat(0.5)
+		);
+	}
+
+	//sRGB images get their colors decoded to linear, but never their alpha
+	#[test]
+	fn from_image_color_spaces() {
+		let image : DynamicImage = DynamicImage::ImageRgba8(RgbaImage::from_fn(
+			3,
+			1,
+			|x : u32, _y : u32| -> Rgba<u8> { Rgba([[0, 10, 255][x as usize], 128, 255, 128]) },
+		));
+
+		let srgb : Texture = Texture::from_image(&image, ColorSpace::Srgb);
+		let linear : Texture = Texture::from_image(&image, ColorSpace::Linear);
+
+		let (dark, half) : (f32, f32) = (10.0 / 255.0, 128.0 / 255.0);
+
+		//Values that dark are on the straight part of the curve
+		let expected : [(Vec4, Vec4); 3] = [
+			(Vec4::new(0.0, 0.2158605, 1.0, half), Vec4::new(0.0, half, 1.0, half)),
+			(Vec4::new(dark / 12.92, 0.2158605, 1.0, half), Vec4::new(dark, half, 1.0, half)),
+			(Vec4::new(1.0, 0.2158605, 1.0, half), Vec4::new(1.0, half, 1.0, half)),
+		];
+
+		for (x, (srgb_texel, linear_texel)) in expected.into_iter().enumerate() {
+			assert!(
+				srgb.texel(x as i32, 0).abs_diff_eq(srgb_texel, 1e-6),
+				"{} isn't {}",
+				srgb.texel(x as i32, 0),
+				srgb_texel
+			);
+			assert_eq!(linear.texel(x as i32, 0), linear_texel);
+		}
+	}
+}
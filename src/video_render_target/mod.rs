@@ -0,0 +1,47 @@
+// Render target that streams frames out
+// as back to back binary PPM images,
+// which ffmpeg can encode into a video.
+
+use std::io::Write;
+
+use crate::pixel::Pixel;
+use crate::render_target::{pixel_to_rgb8, Frame, RenderTarget};
+
+//Pipe stdout into something like `ffmpeg -f image2pipe -c:v ppm -i - out.mp4`, or hand it a
+//file to encode later
+pub struct VideoRenderTarget<W : Write> {
+	output : W,
+}
+
+impl<W : Write> VideoRenderTarget<W> {
+	pub fn new(output : W) -> VideoRenderTarget<W> {
+		VideoRenderTarget {
+			output,
+		}
+	}
+
+	pub fn into_inner(self: VideoRenderTarget<W>) -> W {
+		self.output
+	}
+}
+
+impl<W : Write> RenderTarget for VideoRenderTarget<W> {
+	fn present(
+		self: &mut VideoRenderTarget<W>,
+		frame : &Frame,
+	) -> Result<(), String> {
+		let mut data : Vec<u8> =
+			format!("P6\n{} {}\n255\n", frame.width, frame.height).into_bytes();
+
+		data.reserve(frame.color.len() * 3);
+
+		frame.color.iter().for_each(|p : &Pixel| -> () {
+			data.extend_from_slice(&pixel_to_rgb8(p));
+		});
+
+		self.output
+			.write_all(&data)
+			.and_then(|_ : ()| -> std::io::Result<()> { self.output.flush() })
+			.map_err(|e : std::io::Error| -> String { e.to_string() })
+	}
+}
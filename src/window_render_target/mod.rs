@@ -1,10 +1,9 @@
 use std::collections::HashSet;
 use std::num::NonZeroU32;
-use std::ops::{Add, Mul};
 use std::rc::Rc;
 
 use glam::{Mat4, Vec3};
-use softbuffer::{Buffer, Context, Surface};
+use softbuffer::{Buffer, Context, SoftBufferError, Surface};
 use winit::application::ApplicationHandler;
 use winit::dpi::{PhysicalSize, Size};
 use winit::error::EventLoopError;
@@ -19,6 +18,7 @@ use winit::keyboard::{KeyCode, PhysicalKey};
 use winit::window::{Fullscreen, Icon, Window, WindowAttributes, WindowId};
 
 use crate::pixel::Pixel;
+use crate::render_target::{pixel_to_u32, Frame, RenderTarget};
 use crate::renderer::Renderer;
 
 struct WindowState {
@@ -38,8 +38,8 @@ impl WindowState {
 	}
 }
 
-pub struct WindowRenderTarget<'a, V, TE, P, CE> {
-	source : &'a mut Renderer<V, TE, P, CE>,
+pub struct WindowRenderTarget<'a> {
+	source : &'a mut Renderer,
 	//Internal windowing systems need to be inited by winit's application handler callback, so it
 	//must be behind an option
 	window_state : Option<WindowState>,
@@ -47,21 +47,46 @@ pub struct WindowRenderTarget<'a, V, TE, P, CE> {
 	keyboard_state : HashSet<KeyCode>,
 }
 
-impl<'a, V, TE, P, CE> WindowRenderTarget<'a, V, TE, P, CE>
-where
-	V : Clone + Copy,
-	TE : Clone,
-	P : Clone + Copy + Mul<f32, Output = P> + Add<Output = P>,
-	CE : Clone,
-{
+impl RenderTarget for WindowState {
+	fn present(
+		self: &mut WindowState,
+		frame : &Frame,
+	) -> Result<(), String> {
+		// Correct internal surface size
+		self.surface
+			.resize(
+				NonZeroU32::new(frame.width).ok_or("Width should be non-zero")?,
+				NonZeroU32::new(frame.height).ok_or("Height should be non-zero")?,
+			)
+			.map_err(|e : SoftBufferError| -> String { e.to_string() })?;
+
+		let mut buffer : Buffer<OwnedDisplayHandle, Rc<Window>> = self
+			.surface
+			.buffer_mut()
+			.map_err(|e : SoftBufferError| -> String { e.to_string() })?;
+
+		buffer
+			.iter_mut()
+			.zip(frame.color.iter())
+			.for_each(|(u, p) : (&mut u32, &Pixel)| -> () {
+				*u = pixel_to_u32(p);
+			});
+
+		buffer
+			.present()
+			.map_err(|e : SoftBufferError| -> String { e.to_string() })
+	}
+}
+
+impl<'a> WindowRenderTarget<'a> {
 	pub fn new(
-		source : &'a mut Renderer<V, TE, P, CE>
-	) -> Result<WindowRenderTarget<'a, V, TE, P, CE>, String> {
+		source : &'a mut Renderer
+	) -> Result<WindowRenderTarget<'a>, String> {
 		let event_loop : EventLoop<()> = EventLoop::new().unwrap();
 
 		event_loop.set_control_flow(ControlFlow::Poll);
 
-		let mut ret : WindowRenderTarget<'a, V, TE, P, CE> = WindowRenderTarget {
+		let mut ret : WindowRenderTarget<'a> = WindowRenderTarget {
 			source,
 			window_state : None,
 			keyboard_state : HashSet::new(),
@@ -73,18 +98,60 @@ where
 
 		Ok(ret)
 	}
+
+	//Flies the renderer's camera around based on the keys currently held down
+	fn apply_camera_controls(self: &mut WindowRenderTarget<'a>) -> () {
+		let mut camera_pos_change : Vec3 = Vec3::ZERO;
+		let mut camera_horiz_angle_change : f32 = 0_f32;
+		let mut camera_vert_angle_change : f32 = 0_f32;
+
+		let movement_amount : f32 = 0.01;
+
+		self.keyboard_state.iter().for_each(|kc : &KeyCode| -> () {
+			match kc {
+				KeyCode::KeyW => {
+					camera_pos_change.z -= movement_amount;
+				},
+				KeyCode::KeyA => {
+					camera_pos_change.x += movement_amount;
+				},
+				KeyCode::KeyS => {
+					camera_pos_change.z += movement_amount;
+				},
+				KeyCode::KeyD => {
+					camera_pos_change.x -= movement_amount;
+				},
+
+				KeyCode::Space => {
+					camera_pos_change.y -= movement_amount;
+				},
+
+				KeyCode::ShiftLeft => {
+					camera_pos_change.y += movement_amount;
+				},
+
+				KeyCode::ArrowLeft => camera_horiz_angle_change -= movement_amount,
+
+				KeyCode::ArrowRight => camera_horiz_angle_change += movement_amount,
+
+				KeyCode::ArrowUp => camera_vert_angle_change += movement_amount,
+
+				KeyCode::ArrowDown => camera_vert_angle_change -= movement_amount,
+
+				_ => {},
+			}
+		});
+
+		self.source.camera.camera_mat *= Mat4::from_translation(camera_pos_change);
+
+		// * Mat4::from_rotation_y(camera_horiz_angle_change)
+		//* Mat4::from_rotation_x(camera_vert_angle_change)
+	}
 }
 
-impl<'a, V, TE, P, CE> ApplicationHandler
-	for WindowRenderTarget<'a, V, TE, P, CE>
-where
-	V : Clone + Copy,
-	TE : Clone,
-	P : Clone + Copy + Mul<f32, Output = P> + Add<Output = P>,
-	CE : Clone,
-{
+impl<'a> ApplicationHandler for WindowRenderTarget<'a> {
 	fn resumed(
-		self: &mut WindowRenderTarget<'a, V, TE, P, CE>,
+		self: &mut WindowRenderTarget<'a>,
 		event_loop : &ActiveEventLoop,
 	) -> () {
 		//Initialize the windowstate now that we have the event loop do the window creating
@@ -135,7 +202,7 @@ where
 	}
 
 	fn window_event(
-		self: &mut WindowRenderTarget<'a, V, TE, P, CE>,
+		self: &mut WindowRenderTarget<'a>,
 		event_loop : &ActiveEventLoop,
 		_id : WindowId,
 		event : WindowEvent,
@@ -143,95 +210,21 @@ where
 		match event {
 			WindowEvent::RedrawRequested => {
 				//Respond to user input
-				let mut camera_pos_change : Vec3 = Vec3::ZERO;
-				let mut camera_horiz_angle_change : f32 = 0_f32;
-				let mut camera_vert_angle_change : f32 = 0_f32;
-
-				let movement_amount : f32 = 0.01;
-
-				self.keyboard_state.iter().for_each(|kc : &KeyCode| -> () {
-					match kc {
-						KeyCode::KeyW => {
-							camera_pos_change.z -= movement_amount;
-						},
-						KeyCode::KeyA => {
-							camera_pos_change.x += movement_amount;
-						},
-						KeyCode::KeyS => {
-							camera_pos_change.z += movement_amount;
-						},
-						KeyCode::KeyD => {
-							camera_pos_change.x -= movement_amount;
-						},
-
-						KeyCode::Space => {
-							camera_pos_change.y -= movement_amount;
-						},
-
-						KeyCode::ShiftLeft => {
-							camera_pos_change.y += movement_amount;
-						},
-
-						KeyCode::ArrowLeft => camera_horiz_angle_change -= movement_amount,
-
-						KeyCode::ArrowRight => camera_horiz_angle_change += movement_amount,
-
-						KeyCode::ArrowUp => camera_vert_angle_change += movement_amount,
-
-						KeyCode::ArrowDown => camera_vert_angle_change -= movement_amount,
-
-						_ => {},
-					}
-				});
-
-				self.source.camera.camera_mat *=
-					Mat4::from_translation(camera_pos_change);
-
-				// * Mat4::from_rotation_y(camera_horiz_angle_change)
-				//* Mat4::from_rotation_x(camera_vert_angle_change)
+				self.apply_camera_controls();
 
 				//Advanced render update function and have it draw to its internal frame buffer
 				self.source.frame_step();
 
-				// Correct internal surface size
-				self
-					.window_state
-					.as_mut()
-					.expect("Window should be inited by first draw request")
-					.surface
-					.resize(
-						NonZeroU32::new(self.source.width())
-							.expect("Width should be non-zero"),
-						NonZeroU32::new(self.source.height())
-							.expect("Height should be non-zero"),
-					)
-					.expect("Surface should be resizable");
-
-				let mut buffer : Buffer<OwnedDisplayHandle, Rc<Window>> = self
+				let window_state : &mut WindowState = self
 					.window_state
 					.as_mut()
-					.expect("Window should be inited by first draw request")
-					.surface
-					.buffer_mut()
-					.expect("Buffer should be accessible");
-
-				buffer
-					.iter_mut()
-					.zip(self.source.frame_buffer.iter())
-					.for_each(|(u, p) : (&mut u32, &Pixel)| -> () {
-						*u = ((p.x * u8::MAX as f32).round() as u32) << 16
-							| ((p.y * u8::MAX as f32).round() as u32) << 8
-							| ((p.z * u8::MAX as f32).round() as u32);
-					});
-
-				buffer.present().expect("Buffer presenting should not fail");
-
-				self
-					.window_state
-					.as_mut()
-					.expect("Renderer should have valid window state by now")
-					.window
-					.request_redraw();
+					.expect("Window should be inited by first draw request");
+
+				window_state
+					.present(&self.source.frame())
+					.expect("Presenting to the window should not fail");
+
+				window_state.window.request_redraw();
 			},
 
 			WindowEvent::KeyboardInput {